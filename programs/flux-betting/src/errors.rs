@@ -23,13 +23,13 @@ pub enum FluxError {
     #[msg("Bet period ended")]
     BetPeriodEnded,
     
-    #[msg("Only bet creator can resolve bet")]
+    #[msg("Member's role does not allow resolving this bet")]
     UnauthorizedResolver,
     
     #[msg("Fee percentage must be 10000 or less (100%)")]
     InvalidFeePercentage,
     
-    #[msg("Only group admin can create bets")]
+    #[msg("Only members with a creator role can create bets")]
     UnauthorizedBetCreator,
     
     #[msg("User is not a member of the group")]
@@ -49,4 +49,217 @@ pub enum FluxError {
     
     #[msg("Bet amount below minimum")]
     BetAmountBelowMinimum,
+    
+    #[msg("Fixed odds bets require odds for every option")]
+    OddsRequired,
+    
+    #[msg("Bet has been cancelled")]
+    BetCancelled,
+    
+    #[msg("Bet has not been cancelled or resolved without a winning stake")]
+    BetNotCancelled,
+    
+    #[msg("Only the bet creator before the bet ends, or the platform admin, can cancel bet")]
+    UnauthorizedCanceller,
+    
+    #[msg("Refund already claimed")]
+    RefundAlreadyClaimed,
+    
+    #[msg("Bet period has not ended yet")]
+    BetNotEnded,
+    
+    #[msg("Bet is not resolved by an oracle")]
+    OracleNotConfigured,
+    
+    #[msg("Oracle resolved bets can't be resolved manually")]
+    OracleResolvedBet,
+    
+    #[msg("Invalid oracle price account")]
+    InvalidOracleAccount,
+    
+    #[msg("Oracle price is stale")]
+    StaleOraclePrice,
+    
+    #[msg("Oracle price must be positive")]
+    InvalidOraclePrice,
+    
+    #[msg("Oracle price confidence interval too wide")]
+    OracleConfidenceTooWide,
+    
+    #[msg("Price ranges must define one range per option")]
+    PriceRangeCountMismatch,
+    
+    #[msg("Price range lower bound must be below its upper bound")]
+    InvalidPriceRange,
+    
+    #[msg("Price ranges leave a gap between options")]
+    PriceRangeGap,
+    
+    #[msg("Price ranges overlap between options")]
+    PriceRangeOverlap,
+    
+    #[msg("Price is outside every option's price range")]
+    PriceOutsideRanges,
+    
+    #[msg("Oracle resolved bets require price ranges")]
+    PriceRangesRequired,
+    
+    #[msg("Winning option does not match the actual price")]
+    InconsistentResolution,
+    
+    #[msg("Challenge period can't be negative")]
+    InvalidChallengePeriod,
+    
+    #[msg("An outcome has already been proposed")]
+    ResolutionAlreadyProposed,
+    
+    #[msg("No outcome has been proposed")]
+    ResolutionNotProposed,
+    
+    #[msg("Challenge period has ended")]
+    ChallengePeriodEnded,
+    
+    #[msg("Challenge period is still active")]
+    ChallengePeriodActive,
+    
+    #[msg("Proposed outcome is already disputed")]
+    AlreadyDisputed,
+    
+    #[msg("Proposed outcome is not disputed")]
+    NotDisputed,
+    
+    #[msg("Only platform admin or arbiter can settle disputes")]
+    UnauthorizedArbiter,
+    
+    #[msg("Only platform admin can perform this action")]
+    UnauthorizedPlatformAdmin,
+    
+    #[msg("Token account owner mismatch")]
+    InvalidTokenAccountOwner,
+    
+    #[msg("Cash-out is not enabled for this bet")]
+    CashOutDisabled,
+    
+    #[msg("Cash-out return must be 10000 or less and max at least min")]
+    InvalidCashOutCurve,
+    
+    #[msg("Cash-out amount must be positive and within the position")]
+    InvalidCashOutAmount,
+    
+    #[msg("Withdrawal exceeds accrued fees")]
+    InsufficientAccruedFees,
+    
+    #[msg("Token account mint mismatch")]
+    InvalidMint,
+    
+    #[msg("Accounts for the bet's settlement asset were not provided")]
+    SettlementAccountsMissing,
+    
+    #[msg("Recipient does not match the bet or platform")]
+    InvalidRecipient,
+    
+    #[msg("Only group admin can perform this action")]
+    UnauthorizedGroupAdmin,
+    
+    #[msg("Group admin can't leave or be removed from the group")]
+    GroupAdminCannotLeave,
+    
+    #[msg("Member still has open positions in the group")]
+    MemberHasOpenPositions,
+    
+    #[msg("Only losing positions can be closed")]
+    PositionNotLost,
+    
+    #[msg("An invite is required to join this group")]
+    InviteRequired,
+    
+    #[msg("Invite does not match the group or code")]
+    InvalidInvite,
+    
+    #[msg("Invite has expired")]
+    InviteExpired,
+    
+    #[msg("Invite has no uses left")]
+    InviteExhausted,
+    
+    #[msg("Invite must allow at least one use and expire in the future")]
+    InvalidInviteTerms,
+    
+    #[msg("Group does not take join requests")]
+    ApprovalNotRequired,
+    
+    #[msg("Join request has not been approved")]
+    JoinRequestNotApproved,
+    
+    #[msg("Admin role is only held by the group admin")]
+    InvalidRole,
+    
+    #[msg("Group admin's role can't be changed")]
+    GroupAdminRoleLocked,
+    
+    #[msg("Proposed admin already administers the group")]
+    AlreadyGroupAdmin,
+    
+    #[msg("Signer is not the proposed group admin")]
+    NotPendingGroupAdmin,
+    
+    #[msg("Group still has active bets")]
+    GroupHasActiveBets,
+    
+    #[msg("Group still has members other than the admin")]
+    GroupHasMembers,
+    
+    #[msg("Signer is not the proposed platform admin")]
+    NotPendingPlatformAdmin,
+    
+    #[msg("Platform is paused")]
+    PlatformPaused,
+    
+    #[msg("Wager would leave the bet unable to cover its fixed odds payouts")]
+    LiabilityExceeded,
+    
+    #[msg("Parimutuel bets don't take a bankroll")]
+    BankrollNotSupported,
+    
+    #[msg("Only the bet creator or group admin can fund the bet, and only one of them per bet")]
+    UnauthorizedFunder,
+    
+    #[msg("Only the account that funded the bankroll can withdraw it")]
+    NotBetHouse,
+    
+    #[msg("Bankroll already withdrawn")]
+    BankrollAlreadyWithdrawn,
+    
+    #[msg("LMSR bets need a liquidity parameter above zero")]
+    LiquidityRequired,
+    
+    #[msg("Early cash-out is not available for LMSR or dynamic odds bets")]
+    CashOutNotSupported,
+    
+    #[msg("Trade falls outside the range the market maker can price")]
+    LmsrOutOfRange,
+    
+    #[msg("LMSR bet needs a bankroll of at least b ln n before it can trade")]
+    MarketNotFunded,
+    
+    #[msg("Overround must be 10000 or less (100%)")]
+    InvalidOverround,
+    
+    #[msg("Wager's expected payout fell below the minimum accepted")]
+    SlippageExceeded,
+    
+    #[msg("Nothing reached the vault once the transfer fee was withheld")]
+    InvalidAmount,
+    
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+    
+    #[msg("Oracle price exponent differs from the one the bet was configured with")]
+    OracleExponentMismatch,
+    
+    #[msg("Mint has a Token-2022 extension other than a transfer fee")]
+    UnsupportedMintExtension,
+    
+    #[msg("A challenge period needs a non-zero dispute bond")]
+    DisputeBondRequired,
 } 
\ No newline at end of file
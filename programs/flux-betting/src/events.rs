@@ -0,0 +1,294 @@
+use anchor_lang::prelude::*;
+use crate::state::{GroupRole, GroupVisibility, PayoutMode, SettlementAsset};
+
+#[event]
+pub struct PlatformInitialized {
+    pub platform: Pubkey,
+    pub admin: Pubkey,
+    pub treasury: Pubkey,
+    pub fee_percentage: u16,
+    pub oracle_program: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultCreated {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ArbiterUpdated {
+    pub arbiter: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeUpdated {
+    pub previous_fee_percentage: u16,
+    pub fee_percentage: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlatformAdminProposed {
+    pub admin: Pubkey,
+    pub proposed_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlatformAdminTransferred {
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TreasuryUpdated {
+    pub previous_treasury: Pubkey,
+    pub treasury: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlatformPauseUpdated {
+    pub paused: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeesWithdrawn {
+    pub mint: Pubkey, // Default for native SOL fees
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GroupCreated {
+    pub group: Pubkey,
+    pub admin: Pubkey,
+    pub name: String,
+    pub visibility: GroupVisibility,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GroupVisibilityUpdated {
+    pub group: Pubkey,
+    pub visibility: GroupVisibility,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InviteCreated {
+    pub group: Pubkey,
+    pub invite: Pubkey,
+    pub expires_at: i64,
+    pub max_uses: u32,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InviteRevoked {
+    pub group: Pubkey,
+    pub invite: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct JoinRequested {
+    pub group: Pubkey,
+    pub user: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct JoinRequestReviewed {
+    pub group: Pubkey,
+    pub user: Pubkey,
+    pub approved: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MemberJoined {
+    pub group: Pubkey,
+    pub user: Pubkey,
+    pub member_count: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MemberLeft {
+    pub group: Pubkey,
+    pub user: Pubkey,
+    pub removed_by: Option<Pubkey>, // Set when the group admin removed the member
+    pub member_count: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RoleUpdated {
+    pub group: Pubkey,
+    pub user: Pubkey,
+    pub role: GroupRole,
+    pub updated_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GroupAdminProposed {
+    pub group: Pubkey,
+    pub admin: Pubkey,
+    pub proposed_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GroupAdminTransferred {
+    pub group: Pubkey,
+    pub previous_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct GroupClosed {
+    pub group: Pubkey,
+    pub admin: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetCreated {
+    pub bet: Pubkey,
+    pub group: Pubkey,
+    pub creator: Pubkey,
+    pub mint: Pubkey,
+    pub settlement_asset: SettlementAsset,
+    pub payout_mode: PayoutMode,
+    pub option_count: u8,
+    pub end_time: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetFunded {
+    pub bet: Pubkey,
+    pub funder: Pubkey,
+    pub amount: u64, // Credited to the bankroll, net of any transfer fee
+    pub bankroll: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetPlaced {
+    pub bet: Pubkey,
+    pub user: Pubkey,
+    pub option_index: u8,
+    pub amount: u64, // Credited to the position, net of any transfer fee
+    pub shares: u64, // Outcome shares bought, LMSR bets only
+    pub odds: u16, // Odds the stake was taken at, zero for parimutuel and LMSR bets
+    pub total_pool: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OddsUpdated {
+    pub bet: Pubkey,
+    pub odds: Vec<u16>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CashedOut {
+    pub bet: Pubkey,
+    pub user: Pubkey,
+    pub option_index: u8,
+    pub stake: u64,
+    pub payout: u64,
+    pub fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ResolutionProposed {
+    pub bet: Pubkey,
+    pub proposer: Pubkey,
+    pub winning_option: u8,
+    pub actual_price: u64,
+    pub challenge_deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ResolutionDisputed {
+    pub bet: Pubkey,
+    pub disputer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeSettled {
+    pub bet: Pubkey,
+    pub arbiter: Pubkey,
+    pub winning_option: u8,
+    pub proposal_upheld: bool,
+    pub timestamp: i64,
+}
+
+// Emitted once the outcome is final, whichever path resolved it
+#[event]
+pub struct BetResolved {
+    pub bet: Pubkey,
+    pub winning_option: u8,
+    pub actual_price: Option<u64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WinningsClaimed {
+    pub bet: Pubkey,
+    pub user: Pubkey,
+    pub option_index: u8,
+    pub winnings: u64,
+    pub fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PositionClosed {
+    pub bet: Pubkey,
+    pub user: Pubkey,
+    pub option_index: u8,
+    pub loss: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BetCancelled {
+    pub bet: Pubkey,
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct BankrollWithdrawn {
+    pub bet: Pubkey,
+    pub house: Pubkey,
+    pub amount: u64,
+    pub net_result: i64, // Amount less the bankroll, negative when winners took part of it
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RefundClaimed {
+    pub bet: Pubkey,
+    pub user: Pubkey,
+    pub option_index: u8,
+    pub amount: u64,
+    pub timestamp: i64,
+}
@@ -1,21 +1,72 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Transfer};
+use anchor_lang::system_program;
 use crate::errors::FluxError;
+use crate::events::*;
+use crate::lmsr;
+use crate::oracle::load_price_update;
 use crate::state::*;
-use crate::utils::{calculate_winnings, validate_options_and_odds};
+use crate::utils::{
+    accrue_fees, bankroll_remainder, calculate_cash_out_value, dynamic_odds, calculate_fee, calculate_parimutuel_winnings,
+    calculate_winnings, check_solvency, expected_payout, fixed_odds_liability, fixed_odds_payout, settle_bet_record,
+    validate_cash_out_curve, validate_options_and_odds, validate_price_ranges,
+    winning_option_for_price,
+};
 
+#[allow(clippy::too_many_arguments)]
 pub fn create_bet(
     ctx: Context<CreateBet>,
     bet_id: String,
     coin: String,
     description: String,
     options: Vec<String>,
-    odds: Vec<u16>,
+    odds: Option<Vec<u16>>,
     end_time: i64,
     min_bet_amount: u64,
+    payout_mode: PayoutMode,
+    oracle: Option<OracleConfig>,
+    price_ranges: Option<Vec<PriceRange>>,
+    challenge_period: i64,
+    dispute_bond: u64,
+    cash_out: Option<CashOutCurve>,
+    settlement_asset: SettlementAsset,
+    liquidity: Option<u64>,
+    overround_bps: Option<u16>,
 ) -> Result<()> {
 
-    validate_options_and_odds(&options, &odds)?;
+    validate_options_and_odds(&options, odds.as_deref(), payout_mode)?;
+    
+    let price_ranges = price_ranges.unwrap_or_default();
+    if !price_ranges.is_empty() {
+        validate_price_ranges(&options, &price_ranges)?;
+    }
+    
+    // the oracle needs price ranges to pick the winning option itself
+    if oracle.is_some() {
+        require!(!price_ranges.is_empty(), FluxError::PriceRangesRequired);
+    }
+    
+    require!(challenge_period >= 0, FluxError::InvalidChallengePeriod);
+    
+    // a free dispute would let anyone stall every payout for the whole window
+    if challenge_period > 0 {
+        require!(dispute_bond > 0, FluxError::DisputeBondRequired);
+    }
+    
+    if let Some(curve) = &cash_out {
+        validate_cash_out_curve(curve)?;
+    }
+    
+    // the market maker prices shares off b, and a cash-out curve can't value them
+    if payout_mode == PayoutMode::Lmsr {
+        require!(liquidity.is_some_and(|b| b > 0), FluxError::LiquidityRequired);
+        require!(cash_out.is_none(), FluxError::CashOutNotSupported);
+    }
+    
+    // nor can it value positions taken at odds that have since moved
+    if payout_mode == PayoutMode::DynamicOdds {
+        require!(overround_bps.unwrap_or(0) <= 10000, FluxError::InvalidOverround);
+        require!(cash_out.is_none(), FluxError::CashOutNotSupported);
+    }
     
     let current_time = Clock::get()?.unix_timestamp;    
     require!(end_time > current_time, FluxError::BetPeriodEnded);
@@ -24,83 +75,335 @@ pub fn create_bet(
     let group = &mut ctx.accounts.group;
     let creator = &ctx.accounts.creator;
     let platform = &mut ctx.accounts.platform;
-    let user_profile = &mut ctx.accounts.user_profile;
     
-    let mut bets_per_option = Vec::new();
-    for _ in 0..options.len() {
-        bets_per_option.push(0);
-    }
+    let mint = match settlement_asset {
+        SettlementAsset::Token => {
+            let mint = ctx.accounts.mint.as_ref().ok_or(FluxError::SettlementAccountsMissing)?;
+            mint.key()
+        }
+        SettlementAsset::NativeSol => {
+            let escrow = ctx.accounts.escrow.as_ref().ok_or(FluxError::SettlementAccountsMissing)?;
+            
+            // the escrow is funded up to rent exemption so it can be paid out down to the reserve
+            let rent_reserve = Rent::get()?.minimum_balance(0);
+            let shortfall = rent_reserve.saturating_sub(escrow.lamports());
+            if shortfall > 0 {
+                system_program::transfer(
+                    CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: creator.to_account_info(),
+                            to: escrow.to_account_info(),
+                        },
+                    ),
+                    shortfall,
+                )?;
+            }
+            
+            Pubkey::default()
+        }
+    };
+    
+    let bets_per_option = vec![0; options.len()];
     
     bet.id = bet_id.clone();
     bet.group = group.key();
     bet.creator = creator.key();
+    bet.mint = mint;
+    bet.settlement_asset = settlement_asset;
     bet.coin = coin.clone();
     bet.description = description;
     bet.options = options;
-    bet.odds = odds;
+    bet.odds = odds.unwrap_or_default();
     bet.min_bet_amount = min_bet_amount;
     bet.total_pool = 0;
+    bet.liabilities = bets_per_option.clone();
     bet.bets_per_option = bets_per_option;
+    bet.bankroll = 0;
+    bet.house = None;
+    bet.bankroll_withdrawn = false;
+    bet.liquidity = liquidity.filter(|_| payout_mode == PayoutMode::Lmsr).unwrap_or(0);
+    bet.overround_bps = overround_bps.filter(|_| payout_mode == PayoutMode::DynamicOdds).unwrap_or(0);
+    // later fee changes don't touch bets already open
+    bet.fee_percentage = platform.fee_percentage;
     bet.created_at = current_time;
     bet.end_time = end_time;
     bet.resolved = false;
+    bet.cancelled = false;
     bet.winning_option = None;
     bet.actual_price = None;
+    bet.payout_mode = payout_mode;
+    bet.oracle = oracle;
+    bet.price_ranges = price_ranges;
+    bet.challenge_period = challenge_period;
+    bet.dispute_bond = dispute_bond;
+    bet.challenge_deadline = None;
+    bet.proposer = None;
+    bet.disputer = None;
+    bet.cash_out = cash_out;
     bet.bump = ctx.bumps.bet;
     
-    group.active_bets.push(bet.key());
-    
-    user_profile.active_bets.push(bet.key());
+    group.active_bet_count = group.active_bet_count.checked_add(1).unwrap();
     
     platform.total_bets = platform.total_bets.checked_add(1).unwrap();
     
+    emit!(BetCreated {
+        bet: bet.key(),
+        group: group.key(),
+        creator: creator.key(),
+        mint: bet.mint,
+        settlement_asset,
+        payout_mode,
+        option_count: bet.options.len() as u8,
+        end_time,
+        timestamp: current_time,
+    });
+    
     msg!("Bet '{}' created for coin {} by {}", bet_id, coin, creator.key());
     
     Ok(())
 }
 
+pub fn fund_bet(ctx: Context<FundBet>, amount: u64) -> Result<()> {
+    let funds = ctx.accounts.bet_funds(ctx.bumps.escrow);
+    
+    let received = funds.deposit(
+        &ctx.accounts.funder,
+        ctx.accounts.funder_token_account.as_ref(),
+        amount,
+    )?;
+    
+    let bet = &mut ctx.accounts.bet;
+    let funder = &ctx.accounts.funder;
+    
+    bet.bankroll = bet.bankroll.checked_add(received).unwrap();
+    bet.house = Some(funder.key());
+    
+    emit!(BetFunded {
+        bet: bet.key(),
+        funder: funder.key(),
+        amount: received,
+        bankroll: bet.bankroll,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Bet '{}' bankroll raised by {} to {}", bet.id, received, bet.bankroll);
+    
+    Ok(())
+}
+
 pub fn place_bet(
     ctx: Context<PlaceBet>,
     amount: u64,
     option_index: u8,
+    min_expected_payout: Option<u64>,
 ) -> Result<()> {
+    let funds = ctx.accounts.bet_funds(ctx.bumps.escrow);
+    
+    // only what reaches the vault backs the position when the mint withholds a transfer fee
+    let received = funds.deposit(
+        &ctx.accounts.user,
+        ctx.accounts.user_token_account.as_ref(),
+        amount,
+    )?;
+    require!(received > 0, FluxError::InvalidAmount);
+    
     let bet = &mut ctx.accounts.bet;
     let user = &ctx.accounts.user;
     let user_bet = &mut ctx.accounts.user_bet;
     let user_profile = &mut ctx.accounts.user_profile;
     
-    token::transfer(
-        CpiContext::new(
-            ctx.accounts.token_program.to_account_info(),
-            Transfer {
-                from: ctx.accounts.user_token_account.to_account_info(),
-                to: ctx.accounts.treasury_token_account.to_account_info(),
-                authority: user.to_account_info(),
-            },
-        ),
-        amount,
-    )?;
-    
-    bet.total_pool = bet.total_pool.checked_add(amount).unwrap();
+    bet.total_pool = bet.total_pool.checked_add(received).unwrap();
     bet.bets_per_option[option_index as usize] = bet
         .bets_per_option[option_index as usize]
-        .checked_add(amount)
+        .checked_add(received)
         .unwrap();
     
-    user_bet.user = user.key();
-    user_bet.bet = bet.key();
-    user_bet.amount = amount;
-    user_bet.option_index = option_index;
-    user_bet.claimed = false;
-    user_bet.winnings = None;
-    user_bet.bump = ctx.bumps.user_bet;
+    // fixed and dynamic odds owe the stake times the odds it was taken at,
+    // LMSR owes one token per share bought
+    let (odds, liability, shares) = match bet.payout_mode {
+        PayoutMode::FixedOdds | PayoutMode::DynamicOdds => {
+            let odds = bet.odds[option_index as usize];
+            (odds, fixed_odds_liability(received, odds)?, 0)
+        }
+        PayoutMode::Lmsr => {
+            // the bankroll covers the market maker's worst case before any shares are sold
+            let max_loss = lmsr::max_loss(bet.liquidity, bet.options.len())?;
+            require!(bet.bankroll >= max_loss, FluxError::MarketNotFunded);
+            
+            let shares = lmsr::shares_for_cost(
+                &bet.liabilities,
+                bet.liquidity,
+                option_index as usize,
+                received,
+            )?;
+            (0, shares, shares)
+        }
+        PayoutMode::Parimutuel => (0, 0, 0),
+    };
+    bet.liabilities[option_index as usize] = bet
+        .liabilities[option_index as usize]
+        .checked_add(liability)
+        .unwrap();
+    check_solvency(bet)?;
     
-    if !user_profile.active_bets.contains(&bet.key()) {
-        user_profile.active_bets.push(bet.key());
+    // odds and pool shares can move between the quote and this wager landing,
+    // so the payout net of the fee is held to the caller's floor unless they pass None
+    if let Some(min_expected_payout) = min_expected_payout {
+        let payout = expected_payout(bet, option_index, received, odds, shares)?;
+        require!(payout >= min_expected_payout, FluxError::SlippageExceeded);
     }
     
+    if bet.payout_mode == PayoutMode::DynamicOdds {
+        bet.odds = dynamic_odds(bet)?;
+        
+        emit!(OddsUpdated {
+            bet: bet.key(),
+            odds: bet.odds.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+    }
+    
+    // new position, later wagers on the same option add to it
+    if user_bet.user == Pubkey::default() {
+        user_bet.user = user.key();
+        user_bet.bet = bet.key();
+        user_bet.amount = 0;
+        user_bet.shares = 0;
+        user_bet.locked_odds = 0;
+        user_bet.option_index = option_index;
+        user_bet.claimed = false;
+        user_bet.winnings = None;
+        user_bet.bump = ctx.bumps.user_bet;
+    }
+    
+    // an empty position opens, and counts against the member until settled
+    let opens_position = user_bet.amount == 0;
+    if opens_position {
+        let group_member = &mut ctx.accounts.group_member;
+        group_member.open_positions = group_member.open_positions.checked_add(1).unwrap();
+    }
+    
+    // a top-up blends the new odds into the position by stake
+    if bet.payout_mode == PayoutMode::DynamicOdds && received > 0 {
+        let locked_value = (user_bet.amount as u128) * (user_bet.locked_odds as u128)
+            + (received as u128) * (odds as u128);
+        let stake = (user_bet.amount as u128) + (received as u128);
+        let blended = locked_value.checked_div(stake).ok_or(FluxError::MathOverflow)?;
+        user_bet.locked_odds = u16::try_from(blended).map_err(|_| FluxError::MathOverflow)?;
+    }
+    
+    user_bet.amount = user_bet.amount.checked_add(received).unwrap();
+    user_bet.shares = user_bet.shares.checked_add(shares).unwrap();
+    
+    // first stake on this bet
+    let bet_record = &mut ctx.accounts.bet_record;
+    if bet_record.user == Pubkey::default() {
+        bet_record.user = user.key();
+        bet_record.bet = bet.key();
+        bet_record.open_positions = 0;
+        bet_record.settled = false;
+        bet_record.bump = ctx.bumps.bet_record;
+        
+        user_profile.active_bet_count = user_profile.active_bet_count.checked_add(1).unwrap();
+    } else if opens_position && bet_record.settled {
+        // back in after cashing everything out
+        bet_record.settled = false;
+        user_profile.past_bet_count = user_profile.past_bet_count.checked_sub(1).unwrap();
+        user_profile.active_bet_count = user_profile.active_bet_count.checked_add(1).unwrap();
+    }
+    
+    if opens_position {
+        bet_record.open_positions = bet_record.open_positions.checked_add(1).unwrap();
+    }
+    
+    emit!(BetPlaced {
+        bet: bet.key(),
+        user: user.key(),
+        option_index,
+        amount: received,
+        shares,
+        odds,
+        total_pool: bet.total_pool,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
     msg!("User {} placed bet of {} on option {} for bet '{}'", 
-         user.key(), amount, option_index, bet.id);
+         user.key(), received, option_index, bet.id);
+    
+    Ok(())
+}
+
+pub fn cash_out(ctx: Context<CashOut>, amount: u64) -> Result<()> {
+    let funds = ctx.accounts.bet_funds(ctx.bumps.escrow);
+    
+    let bet = &mut ctx.accounts.bet;
+    let user_bet = &mut ctx.accounts.user_bet;
+    let user = &ctx.accounts.user;
+    
+    require!(
+        amount > 0 && amount <= user_bet.amount,
+        FluxError::InvalidCashOutAmount
+    );
+    
+    let current_time = Clock::get()?.unix_timestamp;
+    let gross_value = calculate_cash_out_value(bet, user_bet.option_index, amount, current_time)?;
+    
+    // apply the fee the bet was created with
+    let fee_amount = calculate_fee(gross_value, bet.fee_percentage)?;
+    let payout = gross_value.checked_sub(fee_amount).unwrap();
+    
+    funds.withdraw(
+        &user.to_account_info(),
+        ctx.accounts.user_token_account.as_ref(),
+        payout,
+    )?;
+    funds.retain_fee(&ctx.accounts.fee_ledger.to_account_info(), fee_amount)?;
+    
+    accrue_fees(
+        &mut ctx.accounts.fee_ledger,
+        bet.mint,
+        ctx.bumps.fee_ledger,
+        fee_amount,
+    )?;
+    
+    // the stake leaves the option, the haircut stays in the pool
+    let option_index = user_bet.option_index as usize;
+    bet.bets_per_option[option_index] = bet.bets_per_option[option_index]
+        .checked_sub(amount)
+        .unwrap();
+    bet.total_pool = bet.total_pool.checked_sub(gross_value).unwrap();
+    
+    // the pool shrinks, so every option's payout must still be covered
+    if bet.payout_mode == PayoutMode::FixedOdds {
+        let released = fixed_odds_payout(amount, bet.odds[option_index])?;
+        bet.liabilities[option_index] = bet.liabilities[option_index].saturating_sub(released);
+    }
+    check_solvency(bet)?;
+    
+    user_bet.amount = user_bet.amount.checked_sub(amount).unwrap();
+    
+    if user_bet.amount == 0 {
+        let group_member = &mut ctx.accounts.group_member;
+        group_member.open_positions = group_member.open_positions.checked_sub(1).unwrap();
+        
+        let bet_record = &mut ctx.accounts.bet_record;
+        bet_record.open_positions = bet_record.open_positions.checked_sub(1).unwrap();
+        settle_bet_record(bet_record, &mut ctx.accounts.user_profile);
+    }
+    
+    emit!(CashedOut {
+        bet: bet.key(),
+        user: user.key(),
+        option_index: user_bet.option_index,
+        stake: amount,
+        payout,
+        fee: fee_amount,
+        timestamp: current_time,
+    });
+    
+    msg!("User {} cashed out {} of their stake on option {} for {} in bet '{}'", 
+         user.key(), amount, user_bet.option_index, payout, bet.id);
     
     Ok(())
 }
@@ -110,56 +413,300 @@ pub fn resolve_bet(
     winning_option: u8,
     actual_price: u64,
 ) -> Result<()> {
+    let funds = ctx.accounts.bet_funds(ctx.bumps.escrow);
+    
+    let bet = &mut ctx.accounts.bet;
+    
+    // bets with price ranges derive the winner from the actual price
+    if !bet.price_ranges.is_empty() {
+        let expected_option = winning_option_for_price(&bet.price_ranges, actual_price)?;
+        require!(winning_option == expected_option, FluxError::InconsistentResolution);
+    }
+    
+    // the proposer backs the outcome with the same bond a disputer has to post
+    if bet.dispute_bond > 0 {
+        // grossed up so the bet holds the full bond after any transfer fee
+        funds.deposit_exact(
+            &ctx.accounts.resolver,
+            ctx.accounts.resolver_token_account.as_ref(),
+            bet.dispute_bond,
+        )?;
+    }
+    
+    let current_time = Clock::get()?.unix_timestamp;
+    let challenge_deadline = current_time.checked_add(bet.challenge_period).unwrap();
+    
+    bet.winning_option = Some(winning_option);
+    bet.actual_price = Some(actual_price);
+    bet.challenge_deadline = Some(challenge_deadline);
+    bet.proposer = Some(ctx.accounts.resolver.key());
+    
+    emit!(ResolutionProposed {
+        bet: bet.key(),
+        proposer: ctx.accounts.resolver.key(),
+        winning_option,
+        actual_price,
+        challenge_deadline,
+        timestamp: current_time,
+    });
+    
+    msg!("Bet '{}' outcome proposed with winning option {} and actual price {}, challengeable until {}", 
+         bet.id, winning_option, actual_price, challenge_deadline);
+    
+    Ok(())
+}
+
+pub fn dispute_resolution(ctx: Context<DisputeResolution>) -> Result<()> {
+    let funds = ctx.accounts.bet_funds(ctx.bumps.escrow);
+    
+    let bet = &mut ctx.accounts.bet;
+    let disputer = &ctx.accounts.disputer;
+    
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        current_time < bet.challenge_deadline.unwrap(),
+        FluxError::ChallengePeriodEnded
+    );
+    
+    if bet.dispute_bond > 0 {
+        // grossed up so the bet holds the full bond after any transfer fee
+        funds.deposit_exact(
+            &ctx.accounts.disputer,
+            ctx.accounts.disputer_token_account.as_ref(),
+            bet.dispute_bond,
+        )?;
+    }
+    
+    bet.disputer = Some(disputer.key());
+    
+    emit!(ResolutionDisputed {
+        bet: bet.key(),
+        disputer: disputer.key(),
+        timestamp: current_time,
+    });
+    
+    msg!("User {} disputed the proposed outcome of bet '{}'", disputer.key(), bet.id);
+    
+    Ok(())
+}
+
+pub fn settle_dispute(
+    ctx: Context<SettleDispute>,
+    winning_option: u8,
+    actual_price: u64,
+) -> Result<()> {
+    let funds = ctx.accounts.bet_funds(ctx.bumps.escrow);
+    
     let bet = &mut ctx.accounts.bet;
     let group = &mut ctx.accounts.group;
     
+    if !bet.price_ranges.is_empty() {
+        let expected_option = winning_option_for_price(&bet.price_ranges, actual_price)?;
+        require!(winning_option == expected_option, FluxError::InconsistentResolution);
+    }
+    
+    // the side that was right gets its bond back plus the losing bond
+    let proposal_upheld = bet.winning_option == Some(winning_option);
+    let bond_payout = bet.dispute_bond.checked_mul(2).unwrap();
+    
+    if bond_payout > 0 {
+        let (recipient, recipient_token_account) = if proposal_upheld {
+            (&ctx.accounts.proposer, &ctx.accounts.proposer_token_account)
+        } else {
+            (&ctx.accounts.disputer, &ctx.accounts.disputer_token_account)
+        };
+        
+        funds.withdraw(
+            &recipient.to_account_info(),
+            recipient_token_account.as_ref(),
+            bond_payout,
+        )?;
+    }
+    
     bet.resolved = true;
     bet.winning_option = Some(winning_option);
     bet.actual_price = Some(actual_price);
     
     let bet_key = bet.key();
-    if let Some(index) = group.active_bets.iter().position(|&x| x == bet_key) {
-        group.active_bets.remove(index);
-        group.past_bets.push(bet_key);
+    group.active_bet_count = group.active_bet_count.checked_sub(1).unwrap();
+    group.past_bet_count = group.past_bet_count.checked_add(1).unwrap();
+    
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    emit!(DisputeSettled {
+        bet: bet_key,
+        arbiter: ctx.accounts.arbiter.key(),
+        winning_option,
+        proposal_upheld,
+        timestamp: current_time,
+    });
+    emit!(BetResolved {
+        bet: bet_key,
+        winning_option,
+        actual_price: Some(actual_price),
+        timestamp: current_time,
+    });
+    
+    msg!("Dispute on bet '{}' settled with winning option {}, proposal upheld: {}", 
+         bet.id, winning_option, proposal_upheld);
+    
+    Ok(())
+}
+
+pub fn finalize_resolution(ctx: Context<FinalizeResolution>) -> Result<()> {
+    let funds = ctx.accounts.bet_funds(ctx.bumps.escrow);
+    
+    let bet = &mut ctx.accounts.bet;
+    let group = &mut ctx.accounts.group;
+    
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        current_time >= bet.challenge_deadline.unwrap(),
+        FluxError::ChallengePeriodActive
+    );
+    
+    // undisputed, so the proposer's bond is returned
+    if bet.dispute_bond > 0 {
+        funds.withdraw(
+            &ctx.accounts.proposer.to_account_info(),
+            ctx.accounts.proposer_token_account.as_ref(),
+            bet.dispute_bond,
+        )?;
     }
     
-    msg!("Bet '{}' resolved with winning option {} and actual price {}", 
+    bet.resolved = true;
+    
+    let bet_key = bet.key();
+    group.active_bet_count = group.active_bet_count.checked_sub(1).unwrap();
+    group.past_bet_count = group.past_bet_count.checked_add(1).unwrap();
+    
+    emit!(BetResolved {
+        bet: bet_key,
+        winning_option: bet.winning_option.unwrap(),
+        actual_price: bet.actual_price,
+        timestamp: current_time,
+    });
+    
+    msg!("Bet '{}' finalized with winning option {}", 
+         bet.id, bet.winning_option.unwrap());
+    
+    Ok(())
+}
+
+pub fn resolve_from_oracle(ctx: Context<ResolveFromOracle>) -> Result<()> {
+    let bet = &mut ctx.accounts.bet;
+    let group = &mut ctx.accounts.group;
+    let platform = &ctx.accounts.platform;
+    let oracle = bet.oracle.clone().ok_or(FluxError::OracleNotConfigured)?;
+    
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(current_time >= bet.end_time, FluxError::BetNotEnded);
+    
+    let price_update = load_price_update(&ctx.accounts.price_feed, &platform.oracle_program)?;
+    let message = price_update.price_message;
+    
+    require!(message.feed_id == oracle.feed_id, FluxError::InvalidOracleAccount);
+    
+    // the price must be observed after the bet closed, and close enough to it to be its closing price
+    require!(message.publish_time >= bet.end_time, FluxError::StaleOraclePrice);
+    require!(
+        message.publish_time <= bet.end_time.saturating_add(oracle.max_staleness),
+        FluxError::StaleOraclePrice
+    );
+    
+    // price ranges are compared against the raw price, so its scale must match theirs
+    require!(message.exponent == oracle.exponent, FluxError::OracleExponentMismatch);
+    require!(message.price > 0, FluxError::InvalidOraclePrice);
+    let actual_price = message.price as u64;
+    
+    require!(
+        (message.conf as u128) * 10000 <= (actual_price as u128) * (oracle.max_confidence_bps as u128),
+        FluxError::OracleConfidenceTooWide
+    );
+    
+    let winning_option = winning_option_for_price(&bet.price_ranges, actual_price)?;
+    
+    bet.resolved = true;
+    bet.winning_option = Some(winning_option);
+    bet.actual_price = Some(actual_price);
+    
+    let bet_key = bet.key();
+    group.active_bet_count = group.active_bet_count.checked_sub(1).unwrap();
+    group.past_bet_count = group.past_bet_count.checked_add(1).unwrap();
+    
+    emit!(BetResolved {
+        bet: bet_key,
+        winning_option,
+        actual_price: Some(actual_price),
+        timestamp: current_time,
+    });
+    
+    msg!("Bet '{}' resolved from oracle with winning option {} and actual price {}", 
          bet.id, winning_option, actual_price);
     
     Ok(())
 }
 
 pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
+    let funds = ctx.accounts.bet_funds(ctx.bumps.escrow);
+    
     let bet = &ctx.accounts.bet;
     let user_bet = &mut ctx.accounts.user_bet;
     let user = &ctx.accounts.user;
     let user_profile = &mut ctx.accounts.user_profile;
-    let platform = &ctx.accounts.platform;
     
-    let winning_option_index = bet.winning_option.unwrap();
-    let odds = bet.odds[winning_option_index as usize];
+    let winning_option_index = bet.winning_option.unwrap() as usize;
     
-    let winnings = calculate_winnings(
-        user_bet.amount,
-        odds,
-        bet.total_pool,
-        platform.fee_percentage,
+    let (winnings, fee_amount) = match bet.payout_mode {
+        PayoutMode::FixedOdds => calculate_winnings(
+            user_bet.amount,
+            bet.odds[winning_option_index],
+            bet.total_pool.checked_add(bet.bankroll).unwrap(),
+            bet.fee_percentage,
+        )?,
+        PayoutMode::Parimutuel => calculate_parimutuel_winnings(
+            user_bet.amount,
+            bet.bets_per_option[winning_option_index],
+            bet.total_pool,
+            bet.fee_percentage,
+        )?,
+        PayoutMode::DynamicOdds => calculate_winnings(
+            user_bet.amount,
+            user_bet.locked_odds,
+            bet.total_pool.checked_add(bet.bankroll).unwrap(),
+            bet.fee_percentage,
+        )?,
+        // each winning share pays one token, i.e. 1.0x odds on the shares
+        PayoutMode::Lmsr => calculate_winnings(
+            user_bet.shares,
+            100,
+            bet.total_pool.checked_add(bet.bankroll).unwrap(),
+            bet.fee_percentage,
+        )?,
+    };
+    
+    funds.withdraw(
+        &user.to_account_info(),
+        ctx.accounts.user_token_account.as_ref(),
+        winnings,
     )?;
+    funds.retain_fee(&ctx.accounts.fee_ledger.to_account_info(), fee_amount)?;
     
-    let platform_seeds = &[b"platform".as_ref(), &[platform.bump]];
-    let seeds = &[&platform_seeds[..]];
-    
-    let transfer_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(),
-        Transfer {
-            from: ctx.accounts.treasury_token_account.to_account_info(),
-            to: ctx.accounts.user_token_account.to_account_info(),
-            authority: ctx.accounts.platform.to_account_info(),
-        },
-        seeds,
-    );
+    accrue_fees(
+        &mut ctx.accounts.fee_ledger,
+        ctx.accounts.bet.mint,
+        ctx.bumps.fee_ledger,
+        fee_amount,
+    )?;
+    
+    let bet_record = &mut ctx.accounts.bet_record;
     
-    token::transfer(transfer_ctx, winnings)?;
+    // fully cashed out positions were already settled
+    if user_bet.amount > 0 {
+        let group_member = &mut ctx.accounts.group_member;
+        group_member.open_positions = group_member.open_positions.checked_sub(1).unwrap();
+        bet_record.open_positions = bet_record.open_positions.checked_sub(1).unwrap();
+    }
     
     user_bet.claimed = true;
     user_bet.winnings = Some(winnings);
@@ -170,15 +717,154 @@ pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
         .unwrap();
     
     let bet_key = bet.key();
-    if let Some(index) = user_profile.active_bets.iter().position(|&x| x == bet_key) {
-        user_profile.active_bets.remove(index);
-        if !user_profile.past_bets.contains(&bet_key) {
-            user_profile.past_bets.push(bet_key);
-        }
-    }
+    settle_bet_record(bet_record, user_profile);
+    
+    emit!(WinningsClaimed {
+        bet: bet_key,
+        user: user.key(),
+        option_index: user_bet.option_index,
+        winnings,
+        fee: fee_amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
     
     msg!("User {} claimed {} winnings for bet '{}'", 
          user.key(), winnings, bet.id);
     
     Ok(())
-} 
\ No newline at end of file
+}
+
+pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+    let bet = &ctx.accounts.bet;
+    let user_bet = &ctx.accounts.user_bet;
+    let user = &ctx.accounts.user;
+    let group_member = &mut ctx.accounts.group_member;
+    let user_profile = &mut ctx.accounts.user_profile;
+    
+    let loss = user_bet.amount;
+    
+    // the user may still hold other positions on the bet
+    let bet_record = &mut ctx.accounts.bet_record;
+    if loss > 0 {
+        group_member.open_positions = group_member.open_positions.checked_sub(1).unwrap();
+        bet_record.open_positions = bet_record.open_positions.checked_sub(1).unwrap();
+    }
+    
+    user_profile.total_losses = user_profile.total_losses.checked_add(loss).unwrap();
+    
+    settle_bet_record(bet_record, user_profile);
+    
+    emit!(PositionClosed {
+        bet: bet.key(),
+        user: user.key(),
+        option_index: user_bet.option_index,
+        loss,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("User {} closed losing position on option {} for bet '{}'", 
+         user.key(), user_bet.option_index, bet.id);
+    
+    Ok(())
+}
+
+pub fn cancel_bet(ctx: Context<CancelBet>) -> Result<()> {
+    let bet = &mut ctx.accounts.bet;
+    let group = &mut ctx.accounts.group;
+    let authority = &ctx.accounts.authority;
+    
+    bet.cancelled = true;
+    
+    let bet_key = bet.key();
+    group.active_bet_count = group.active_bet_count.checked_sub(1).unwrap();
+    group.past_bet_count = group.past_bet_count.checked_add(1).unwrap();
+    
+    emit!(BetCancelled {
+        bet: bet_key,
+        authority: authority.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Bet '{}' cancelled by {}", bet.id, authority.key());
+    
+    Ok(())
+}
+
+pub fn withdraw_bankroll(ctx: Context<WithdrawBankroll>) -> Result<()> {
+    let funds = ctx.accounts.bet_funds(ctx.bumps.escrow);
+    
+    let bet = &mut ctx.accounts.bet;
+    let house = &ctx.accounts.house;
+    
+    let amount = bankroll_remainder(bet)?;
+    
+    funds.withdraw(
+        &house.to_account_info(),
+        ctx.accounts.house_token_account.as_ref(),
+        amount,
+    )?;
+    
+    // bankroll stays as is, claims still check against it
+    bet.bankroll_withdrawn = true;
+    
+    let net_result = i64::try_from(amount)
+        .map_err(|_| FluxError::MathOverflow)?
+        .checked_sub(i64::try_from(bet.bankroll).map_err(|_| FluxError::MathOverflow)?)
+        .ok_or(FluxError::MathOverflow)?;
+    
+    emit!(BankrollWithdrawn {
+        bet: bet.key(),
+        house: house.key(),
+        amount,
+        net_result,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("House {} withdrew {} from bet '{}' (net {})", 
+         house.key(), amount, bet.id, net_result);
+    
+    Ok(())
+}
+
+pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+    let funds = ctx.accounts.bet_funds(ctx.bumps.escrow);
+    
+    let bet = &ctx.accounts.bet;
+    let user_bet = &mut ctx.accounts.user_bet;
+    let user = &ctx.accounts.user;
+    let user_profile = &mut ctx.accounts.user_profile;
+    
+    let refund = user_bet.amount;
+    
+    // refunds are fee-free
+    funds.withdraw(
+        &user.to_account_info(),
+        ctx.accounts.user_token_account.as_ref(),
+        refund,
+    )?;
+    
+    let bet_record = &mut ctx.accounts.bet_record;
+    if refund > 0 {
+        let group_member = &mut ctx.accounts.group_member;
+        group_member.open_positions = group_member.open_positions.checked_sub(1).unwrap();
+        bet_record.open_positions = bet_record.open_positions.checked_sub(1).unwrap();
+    }
+    
+    user_bet.claimed = true;
+    
+    let bet_key = bet.key();
+    settle_bet_record(bet_record, user_profile);
+    
+    emit!(RefundClaimed {
+        bet: bet_key,
+        user: user.key(),
+        option_index: user_bet.option_index,
+        amount: refund,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("User {} refunded {} for bet '{}'", 
+         user.key(), refund, bet.id);
+    
+    Ok(())
+}
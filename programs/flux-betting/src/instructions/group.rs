@@ -1,10 +1,14 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use crate::errors::FluxError;
+use crate::events::*;
 use crate::state::*;
 
 pub fn create_group(
     ctx: Context<CreateGroup>,
     name: String,
     description: String,
+    visibility: GroupVisibility,
 ) -> Result<()> {
     let group = &mut ctx.accounts.group;
     let admin = &ctx.accounts.admin;
@@ -14,18 +18,28 @@ pub fn create_group(
     group.name = name.clone();
     group.description = description;
     group.admin = admin.key();
-    group.members = vec![admin.key()];
-    group.active_bets = Vec::new();
-    group.past_bets = Vec::new();
+    group.pending_admin = None;
+    group.visibility = visibility;
+    group.member_count = 1;
+    group.active_bet_count = 0;
+    group.past_bet_count = 0;
     group.created_at = Clock::get()?.unix_timestamp;
     group.bump = ctx.bumps.group;
     
+    let group_member = &mut ctx.accounts.group_member;
+    group_member.group = group.key();
+    group_member.user = admin.key();
+    group_member.joined_at = group.created_at;
+    group_member.open_positions = 0;
+    group_member.role = GroupRole::Admin;
+    group_member.bump = ctx.bumps.group_member;
+    
     // new profile
     if user_profile.user == Pubkey::default() {
         user_profile.user = admin.key();
         user_profile.groups = Vec::new();
-        user_profile.active_bets = Vec::new();
-        user_profile.past_bets = Vec::new();
+        user_profile.active_bet_count = 0;
+        user_profile.past_bet_count = 0;
         user_profile.total_winnings = 0;
         user_profile.total_losses = 0;
         user_profile.bump = ctx.bumps.user_profile;
@@ -37,29 +51,75 @@ pub fn create_group(
     
     platform.total_groups = platform.total_groups.checked_add(1).unwrap();
     
+    emit!(GroupCreated {
+        group: group.key(),
+        admin: admin.key(),
+        name: name.clone(),
+        visibility,
+        timestamp: group.created_at,
+    });
+    
     msg!("Group '{}' created by {}", name, admin.key());
     
     Ok(())
 }
 
-pub fn join_group(ctx: Context<JoinGroup>) -> Result<()> {
+pub fn join_group(ctx: Context<JoinGroup>, invite_code: Option<String>) -> Result<()> {
     let group = &mut ctx.accounts.group;
     let user = &ctx.accounts.user;
+    let group_member = &mut ctx.accounts.group_member;
     let user_profile = &mut ctx.accounts.user_profile;
     
-    if group.members.contains(&user.key()) {
+    if group_member.user != Pubkey::default() {
         msg!("User is already a member of this group");
         return Ok(());
     }
     
-    group.members.push(user.key());
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    match group.visibility {
+        GroupVisibility::Open => {}
+        GroupVisibility::InviteOnly => {
+            let invite = ctx.accounts.invite.as_mut().ok_or(FluxError::InviteRequired)?;
+            let invite_code = invite_code.ok_or(FluxError::InviteRequired)?;
+            
+            require!(
+                hash(invite_code.as_bytes()).to_bytes() == invite.code_hash,
+                FluxError::InvalidInvite
+            );
+            require!(current_time < invite.expires_at, FluxError::InviteExpired);
+            require!(invite.uses < invite.max_uses, FluxError::InviteExhausted);
+            
+            invite.uses = invite.uses.checked_add(1).unwrap();
+        }
+        GroupVisibility::ApprovalRequired => {
+            let join_request = ctx
+                .accounts
+                .join_request
+                .as_ref()
+                .ok_or(FluxError::JoinRequestNotApproved)?;
+            require!(join_request.approved, FluxError::JoinRequestNotApproved);
+            
+            // the request is spent once the member joins
+            join_request.close(user.to_account_info())?;
+        }
+    }
+    
+    group_member.group = group.key();
+    group_member.user = user.key();
+    group_member.joined_at = current_time;
+    group_member.open_positions = 0;
+    group_member.role = GroupRole::Member;
+    group_member.bump = ctx.bumps.group_member;
+    
+    group.member_count = group.member_count.checked_add(1).unwrap();
     
     // new profile
     if user_profile.user == Pubkey::default() {
         user_profile.user = user.key();
         user_profile.groups = Vec::new();
-        user_profile.active_bets = Vec::new();
-        user_profile.past_bets = Vec::new();
+        user_profile.active_bet_count = 0;
+        user_profile.past_bet_count = 0;
         user_profile.total_winnings = 0;
         user_profile.total_losses = 0;
         user_profile.bump = ctx.bumps.user_profile;
@@ -67,7 +127,295 @@ pub fn join_group(ctx: Context<JoinGroup>) -> Result<()> {
     
     user_profile.groups.push(group.key());
     
+    emit!(MemberJoined {
+        group: group.key(),
+        user: user.key(),
+        member_count: group.member_count,
+        timestamp: current_time,
+    });
+    
     msg!("User {} joined group '{}'", user.key(), group.name);
     
     Ok(())
-} 
\ No newline at end of file
+}
+
+pub fn leave_group(ctx: Context<LeaveGroup>) -> Result<()> {
+    let group = &mut ctx.accounts.group;
+    let user = &ctx.accounts.user;
+    let user_profile = &mut ctx.accounts.user_profile;
+    
+    group.member_count = group.member_count.checked_sub(1).unwrap();
+    
+    let group_key = group.key();
+    user_profile.groups.retain(|&g| g != group_key);
+    
+    emit!(MemberLeft {
+        group: group_key,
+        user: user.key(),
+        removed_by: None,
+        member_count: group.member_count,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("User {} left group '{}'", user.key(), group.name);
+    
+    Ok(())
+}
+
+pub fn remove_member(ctx: Context<RemoveMember>) -> Result<()> {
+    let group = &mut ctx.accounts.group;
+    let admin = &ctx.accounts.admin;
+    let member = &ctx.accounts.member;
+    let user_profile = &mut ctx.accounts.user_profile;
+    
+    group.member_count = group.member_count.checked_sub(1).unwrap();
+    
+    let group_key = group.key();
+    user_profile.groups.retain(|&g| g != group_key);
+    
+    emit!(MemberLeft {
+        group: group_key,
+        user: member.key(),
+        removed_by: Some(admin.key()),
+        member_count: group.member_count,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("User {} removed from group '{}' by {}", 
+         member.key(), group.name, admin.key());
+    
+    Ok(())
+}
+
+pub fn set_group_visibility(
+    ctx: Context<SetGroupVisibility>,
+    visibility: GroupVisibility,
+) -> Result<()> {
+    let group = &mut ctx.accounts.group;
+    
+    group.visibility = visibility;
+    
+    emit!(GroupVisibilityUpdated {
+        group: group.key(),
+        visibility,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Group '{}' visibility set to {:?}", group.name, visibility);
+    
+    Ok(())
+}
+
+pub fn create_invite(
+    ctx: Context<CreateInvite>,
+    code_hash: [u8; 32],
+    expires_at: i64,
+    max_uses: u32,
+) -> Result<()> {
+    let group = &ctx.accounts.group;
+    let invite = &mut ctx.accounts.invite;
+    
+    let current_time = Clock::get()?.unix_timestamp;
+    require!(
+        max_uses > 0 && expires_at > current_time,
+        FluxError::InvalidInviteTerms
+    );
+    
+    invite.group = group.key();
+    invite.code_hash = code_hash;
+    invite.expires_at = expires_at;
+    invite.max_uses = max_uses;
+    invite.uses = 0;
+    invite.bump = ctx.bumps.invite;
+    
+    emit!(InviteCreated {
+        group: group.key(),
+        invite: invite.key(),
+        expires_at,
+        max_uses,
+        timestamp: current_time,
+    });
+    
+    msg!("Invite for group '{}' created with {} uses", group.name, max_uses);
+    
+    Ok(())
+}
+
+pub fn revoke_invite(ctx: Context<RevokeInvite>) -> Result<()> {
+    let group = &ctx.accounts.group;
+    let invite = &ctx.accounts.invite;
+    
+    emit!(InviteRevoked {
+        group: group.key(),
+        invite: invite.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Invite for group '{}' revoked after {} uses", group.name, invite.uses);
+    
+    Ok(())
+}
+
+pub fn request_to_join(ctx: Context<RequestToJoin>) -> Result<()> {
+    let group = &ctx.accounts.group;
+    let user = &ctx.accounts.user;
+    let join_request = &mut ctx.accounts.join_request;
+    
+    let current_time = Clock::get()?.unix_timestamp;
+    
+    join_request.group = group.key();
+    join_request.user = user.key();
+    join_request.requested_at = current_time;
+    join_request.approved = false;
+    join_request.bump = ctx.bumps.join_request;
+    
+    emit!(JoinRequested {
+        group: group.key(),
+        user: user.key(),
+        timestamp: current_time,
+    });
+    
+    msg!("User {} requested to join group '{}'", user.key(), group.name);
+    
+    Ok(())
+}
+
+pub fn approve_join_request(ctx: Context<ApproveJoinRequest>) -> Result<()> {
+    let group = &ctx.accounts.group;
+    let join_request = &mut ctx.accounts.join_request;
+    
+    join_request.approved = true;
+    
+    emit!(JoinRequestReviewed {
+        group: group.key(),
+        user: join_request.user,
+        approved: true,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Join request from {} approved for group '{}'", join_request.user, group.name);
+    
+    Ok(())
+}
+
+pub fn reject_join_request(ctx: Context<RejectJoinRequest>) -> Result<()> {
+    let group = &ctx.accounts.group;
+    let join_request = &ctx.accounts.join_request;
+    
+    emit!(JoinRequestReviewed {
+        group: group.key(),
+        user: join_request.user,
+        approved: false,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Join request from {} rejected for group '{}'", join_request.user, group.name);
+    
+    Ok(())
+}
+
+pub fn add_role(ctx: Context<UpdateRole>, role: GroupRole) -> Result<()> {
+    require!(role != GroupRole::Admin, FluxError::InvalidRole);
+    
+    let group = &ctx.accounts.group;
+    let group_member = &mut ctx.accounts.group_member;
+    
+    group_member.role = role;
+    
+    emit!(RoleUpdated {
+        group: group.key(),
+        user: group_member.user,
+        role,
+        updated_by: ctx.accounts.admin.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("User {} given role {:?} in group '{}'", group_member.user, role, group.name);
+    
+    Ok(())
+}
+
+pub fn revoke_role(ctx: Context<UpdateRole>) -> Result<()> {
+    let group = &ctx.accounts.group;
+    let group_member = &mut ctx.accounts.group_member;
+    
+    group_member.role = GroupRole::Member;
+    
+    emit!(RoleUpdated {
+        group: group.key(),
+        user: group_member.user,
+        role: GroupRole::Member,
+        updated_by: ctx.accounts.admin.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("User {} reverted to member in group '{}'", group_member.user, group.name);
+    
+    Ok(())
+}
+
+pub fn propose_group_admin(ctx: Context<ProposeGroupAdmin>, new_admin: Pubkey) -> Result<()> {
+    let group = &mut ctx.accounts.group;
+    
+    require!(new_admin != group.admin, FluxError::AlreadyGroupAdmin);
+    
+    group.pending_admin = Some(new_admin);
+    
+    emit!(GroupAdminProposed {
+        group: group.key(),
+        admin: group.admin,
+        proposed_admin: new_admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("User {} proposed as admin of group '{}'", new_admin, group.name);
+    
+    Ok(())
+}
+
+pub fn accept_group_admin(ctx: Context<AcceptGroupAdmin>) -> Result<()> {
+    let group = &mut ctx.accounts.group;
+    let new_admin = &ctx.accounts.new_admin;
+    
+    let previous_admin = group.admin;
+    group.admin = new_admin.key();
+    group.pending_admin = None;
+    
+    // the admin role moves with the group, the previous admin stays a member
+    ctx.accounts.new_admin_member.role = GroupRole::Admin;
+    ctx.accounts.previous_admin_member.role = GroupRole::Member;
+    
+    emit!(GroupAdminTransferred {
+        group: group.key(),
+        previous_admin,
+        new_admin: new_admin.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Group '{}' admin transferred from {} to {}", 
+         group.name, previous_admin, new_admin.key());
+    
+    Ok(())
+}
+
+pub fn close_group(ctx: Context<CloseGroup>) -> Result<()> {
+    let group = &ctx.accounts.group;
+    let admin = &ctx.accounts.admin;
+    let user_profile = &mut ctx.accounts.user_profile;
+    let platform = &mut ctx.accounts.platform;
+    
+    let group_key = group.key();
+    user_profile.groups.retain(|&g| g != group_key);
+    
+    platform.total_groups = platform.total_groups.checked_sub(1).unwrap();
+    
+    emit!(GroupClosed {
+        group: group_key,
+        admin: admin.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Group '{}' closed by {}", group.name, admin.key());
+    
+    Ok(())
+}
@@ -1,13 +1,15 @@
 use anchor_lang::prelude::*;
 use crate::errors::FluxError;
+use crate::events::*;
 use crate::state::*;
+use crate::utils::{transfer_tokens_from_treasury, validate_fee_percentage, validate_mint_extensions};
 
 pub fn initialize_platform(
     ctx: Context<InitializePlatform>,
     fee_percentage: u16,
+    oracle_program: Pubkey,
 ) -> Result<()> {
-    // fee percentage >= to 10000 (100%)
-    require!(fee_percentage <= 10000, FluxError::InvalidFeePercentage);
+    validate_fee_percentage(fee_percentage)?;
     
     let platform = &mut ctx.accounts.platform;
     let admin = &ctx.accounts.admin;
@@ -19,9 +21,195 @@ pub fn initialize_platform(
     platform.total_bets = 0;
     platform.total_users = 0;
     platform.total_groups = 0;
+    platform.oracle_program = oracle_program;
+    platform.arbiter = admin.key();
+    platform.pending_admin = None;
+    platform.paused = false;
     platform.bump = ctx.bumps.platform;
     
+    emit!(PlatformInitialized {
+        platform: platform.key(),
+        admin: admin.key(),
+        treasury: treasury.key(),
+        fee_percentage,
+        oracle_program,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
     msg!("Platform initialized with fee percentage of {}%", fee_percentage as f64 / 100.0);
     
     Ok(())
-} 
\ No newline at end of file
+}
+
+pub fn init_vault(ctx: Context<InitVault>) -> Result<()> {
+    validate_mint_extensions(&ctx.accounts.mint.to_account_info())?;
+    
+    emit!(VaultCreated {
+        mint: ctx.accounts.mint.key(),
+        vault: ctx.accounts.vault.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Vault {} created for mint {}", 
+         ctx.accounts.vault.key(), ctx.accounts.mint.key());
+    
+    Ok(())
+}
+
+pub fn set_arbiter(ctx: Context<SetArbiter>, arbiter: Pubkey) -> Result<()> {
+    let platform = &mut ctx.accounts.platform;
+    
+    platform.arbiter = arbiter;
+    
+    emit!(ArbiterUpdated {
+        arbiter,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Platform arbiter set to {}", arbiter);
+    
+    Ok(())
+}
+
+pub fn update_fee(ctx: Context<UpdatePlatform>, fee_percentage: u16) -> Result<()> {
+    validate_fee_percentage(fee_percentage)?;
+    
+    let platform = &mut ctx.accounts.platform;
+    let previous_fee_percentage = platform.fee_percentage;
+    
+    platform.fee_percentage = fee_percentage;
+    
+    emit!(FeeUpdated {
+        previous_fee_percentage,
+        fee_percentage,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Platform fee percentage changed from {}% to {}%", 
+         previous_fee_percentage as f64 / 100.0, fee_percentage as f64 / 100.0);
+    
+    Ok(())
+}
+
+pub fn propose_platform_admin(ctx: Context<UpdatePlatform>, new_admin: Pubkey) -> Result<()> {
+    let platform = &mut ctx.accounts.platform;
+    
+    platform.pending_admin = Some(new_admin);
+    
+    emit!(PlatformAdminProposed {
+        admin: platform.admin,
+        proposed_admin: new_admin,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("User {} proposed as platform admin", new_admin);
+    
+    Ok(())
+}
+
+pub fn accept_platform_admin(ctx: Context<AcceptPlatformAdmin>) -> Result<()> {
+    let platform = &mut ctx.accounts.platform;
+    let new_admin = &ctx.accounts.new_admin;
+    
+    let previous_admin = platform.admin;
+    platform.admin = new_admin.key();
+    platform.pending_admin = None;
+    
+    emit!(PlatformAdminTransferred {
+        previous_admin,
+        new_admin: new_admin.key(),
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Platform admin transferred from {} to {}", previous_admin, new_admin.key());
+    
+    Ok(())
+}
+
+pub fn set_treasury(ctx: Context<UpdatePlatform>, treasury: Pubkey) -> Result<()> {
+    let platform = &mut ctx.accounts.platform;
+    let previous_treasury = platform.treasury;
+    
+    platform.treasury = treasury;
+    
+    emit!(TreasuryUpdated {
+        previous_treasury,
+        treasury,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Platform treasury set to {}", treasury);
+    
+    Ok(())
+}
+
+pub fn set_paused(ctx: Context<UpdatePlatform>, paused: bool) -> Result<()> {
+    let platform = &mut ctx.accounts.platform;
+    
+    platform.paused = paused;
+    
+    emit!(PlatformPauseUpdated {
+        paused,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Platform {}", if paused { "paused" } else { "unpaused" });
+    
+    Ok(())
+}
+
+pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+    let platform = &ctx.accounts.platform;
+    let fee_ledger = &mut ctx.accounts.fee_ledger;
+    
+    require!(amount <= fee_ledger.accrued_fees, FluxError::InsufficientAccruedFees);
+    
+    let recipient = if fee_ledger.mint == Pubkey::default() {
+        // native SOL fees are held by the ledger account itself
+        let treasury = ctx.accounts.treasury.as_ref().ok_or(FluxError::SettlementAccountsMissing)?;
+        
+        fee_ledger.sub_lamports(amount)?;
+        treasury.add_lamports(amount)?;
+        
+        treasury.key()
+    } else {
+        let (Some(treasury_token_account), Some(destination_token_account), Some(mint), Some(token_program)) = (
+            &ctx.accounts.treasury_token_account,
+            &ctx.accounts.destination_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.token_program,
+        ) else {
+            return err!(FluxError::SettlementAccountsMissing);
+        };
+        
+        let platform_seeds = &[b"platform".as_ref(), &[platform.bump]];
+        let seeds = &[&platform_seeds[..]];
+        
+        transfer_tokens_from_treasury(
+            token_program,
+            treasury_token_account,
+            mint,
+            destination_token_account,
+            &platform.to_account_info(),
+            seeds,
+            amount,
+        )?;
+        
+        destination_token_account.key()
+    };
+    
+    fee_ledger.accrued_fees = fee_ledger.accrued_fees.checked_sub(amount).unwrap();
+    fee_ledger.total_withdrawn = fee_ledger.total_withdrawn.checked_add(amount).unwrap();
+    
+    emit!(FeesWithdrawn {
+        mint: fee_ledger.mint,
+        recipient,
+        amount,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+    
+    msg!("Withdrew {} fees of mint {} to {}", 
+         amount, fee_ledger.mint, recipient);
+    
+    Ok(())
+}
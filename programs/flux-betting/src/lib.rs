@@ -4,7 +4,9 @@ mod state;
 mod instructions;
 mod errors;
 mod utils;
-mod processor;
+mod oracle;
+mod lmsr;
+mod events;
 
 use state::*;
 
@@ -17,31 +19,135 @@ pub mod flux_betting {
     pub fn initialize_platform(
         ctx: Context<InitializePlatform>,
         fee_percentage: u16,
+        oracle_program: Pubkey,
     ) -> Result<()> {
-        instructions::platform::initialize_platform(ctx, fee_percentage)
+        instructions::platform::initialize_platform(ctx, fee_percentage, oracle_program)
+    }
+
+    pub fn init_vault(ctx: Context<InitVault>) -> Result<()> {
+        instructions::platform::init_vault(ctx)
+    }
+
+    pub fn set_arbiter(ctx: Context<SetArbiter>, arbiter: Pubkey) -> Result<()> {
+        instructions::platform::set_arbiter(ctx, arbiter)
+    }
+
+    pub fn update_fee(ctx: Context<UpdatePlatform>, fee_percentage: u16) -> Result<()> {
+        instructions::platform::update_fee(ctx, fee_percentage)
+    }
+
+    pub fn propose_platform_admin(ctx: Context<UpdatePlatform>, new_admin: Pubkey) -> Result<()> {
+        instructions::platform::propose_platform_admin(ctx, new_admin)
+    }
+
+    pub fn accept_platform_admin(ctx: Context<AcceptPlatformAdmin>) -> Result<()> {
+        instructions::platform::accept_platform_admin(ctx)
+    }
+
+    pub fn set_treasury(ctx: Context<UpdatePlatform>, treasury: Pubkey) -> Result<()> {
+        instructions::platform::set_treasury(ctx, treasury)
+    }
+
+    pub fn set_paused(ctx: Context<UpdatePlatform>, paused: bool) -> Result<()> {
+        instructions::platform::set_paused(ctx, paused)
+    }
+
+    pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
+        instructions::platform::withdraw_fees(ctx, amount)
     }
 
     pub fn create_group(
         ctx: Context<CreateGroup>,
         name: String,
         description: String,
+        visibility: GroupVisibility,
+    ) -> Result<()> {
+        instructions::group::create_group(ctx, name, description, visibility)
+    }
+
+    pub fn join_group(ctx: Context<JoinGroup>, invite_code: Option<String>) -> Result<()> {
+        instructions::group::join_group(ctx, invite_code)
+    }
+
+    pub fn leave_group(ctx: Context<LeaveGroup>) -> Result<()> {
+        instructions::group::leave_group(ctx)
+    }
+
+    pub fn remove_member(ctx: Context<RemoveMember>) -> Result<()> {
+        instructions::group::remove_member(ctx)
+    }
+
+    pub fn set_group_visibility(
+        ctx: Context<SetGroupVisibility>,
+        visibility: GroupVisibility,
     ) -> Result<()> {
-        instructions::group::create_group(ctx, name, description)
+        instructions::group::set_group_visibility(ctx, visibility)
+    }
+
+    pub fn create_invite(
+        ctx: Context<CreateInvite>,
+        code_hash: [u8; 32],
+        expires_at: i64,
+        max_uses: u32,
+    ) -> Result<()> {
+        instructions::group::create_invite(ctx, code_hash, expires_at, max_uses)
+    }
+
+    pub fn revoke_invite(ctx: Context<RevokeInvite>) -> Result<()> {
+        instructions::group::revoke_invite(ctx)
+    }
+
+    pub fn request_to_join(ctx: Context<RequestToJoin>) -> Result<()> {
+        instructions::group::request_to_join(ctx)
+    }
+
+    pub fn approve_join_request(ctx: Context<ApproveJoinRequest>) -> Result<()> {
+        instructions::group::approve_join_request(ctx)
     }
 
-    pub fn join_group(ctx: Context<JoinGroup>) -> Result<()> {
-        instructions::group::join_group(ctx)
+    pub fn reject_join_request(ctx: Context<RejectJoinRequest>) -> Result<()> {
+        instructions::group::reject_join_request(ctx)
     }
 
+    pub fn add_role(ctx: Context<UpdateRole>, role: GroupRole) -> Result<()> {
+        instructions::group::add_role(ctx, role)
+    }
+
+    pub fn revoke_role(ctx: Context<UpdateRole>) -> Result<()> {
+        instructions::group::revoke_role(ctx)
+    }
+
+    pub fn propose_group_admin(ctx: Context<ProposeGroupAdmin>, new_admin: Pubkey) -> Result<()> {
+        instructions::group::propose_group_admin(ctx, new_admin)
+    }
+
+    pub fn accept_group_admin(ctx: Context<AcceptGroupAdmin>) -> Result<()> {
+        instructions::group::accept_group_admin(ctx)
+    }
+
+    pub fn close_group(ctx: Context<CloseGroup>) -> Result<()> {
+        instructions::group::close_group(ctx)
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn create_bet(
         ctx: Context<CreateBet>,
         bet_id: String,
         coin: String,
         description: String,
         options: Vec<String>,
-        odds: Vec<u16>,
+        odds: Option<Vec<u16>>,
         end_time: i64,
         min_bet_amount: u64,
+        payout_mode: PayoutMode,
+        oracle: Option<OracleConfig>,
+        price_ranges: Option<Vec<PriceRange>>,
+        challenge_period: i64,
+        dispute_bond: u64,
+        cash_out: Option<CashOutCurve>,
+        settlement_asset: SettlementAsset,
+        liquidity: Option<u64>,
+        overround_bps: Option<u16>,
     ) -> Result<()> {
         instructions::bet::create_bet(
             ctx,
@@ -52,15 +158,33 @@ pub mod flux_betting {
             odds,
             end_time,
             min_bet_amount,
+            payout_mode,
+            oracle,
+            price_ranges,
+            challenge_period,
+            dispute_bond,
+            cash_out,
+            settlement_asset,
+            liquidity,
+            overround_bps,
         )
     }
 
+    pub fn fund_bet(ctx: Context<FundBet>, amount: u64) -> Result<()> {
+        instructions::bet::fund_bet(ctx, amount)
+    }
+
     pub fn place_bet(
         ctx: Context<PlaceBet>,
         amount: u64,
         option_index: u8,
+        min_expected_payout: Option<u64>,
     ) -> Result<()> {
-        instructions::bet::place_bet(ctx, amount, option_index)
+        instructions::bet::place_bet(ctx, amount, option_index, min_expected_payout)
+    }
+
+    pub fn cash_out(ctx: Context<CashOut>, amount: u64) -> Result<()> {
+        instructions::bet::cash_out(ctx, amount)
     }
 
     pub fn resolve_bet(
@@ -71,7 +195,43 @@ pub mod flux_betting {
         instructions::bet::resolve_bet(ctx, winning_option, actual_price)
     }
 
+    pub fn dispute_resolution(ctx: Context<DisputeResolution>) -> Result<()> {
+        instructions::bet::dispute_resolution(ctx)
+    }
+
+    pub fn settle_dispute(
+        ctx: Context<SettleDispute>,
+        winning_option: u8,
+        actual_price: u64,
+    ) -> Result<()> {
+        instructions::bet::settle_dispute(ctx, winning_option, actual_price)
+    }
+
+    pub fn finalize_resolution(ctx: Context<FinalizeResolution>) -> Result<()> {
+        instructions::bet::finalize_resolution(ctx)
+    }
+
+    pub fn resolve_from_oracle(ctx: Context<ResolveFromOracle>) -> Result<()> {
+        instructions::bet::resolve_from_oracle(ctx)
+    }
+
     pub fn claim_winnings(ctx: Context<ClaimWinnings>) -> Result<()> {
         instructions::bet::claim_winnings(ctx)
     }
+
+    pub fn close_position(ctx: Context<ClosePosition>) -> Result<()> {
+        instructions::bet::close_position(ctx)
+    }
+
+    pub fn cancel_bet(ctx: Context<CancelBet>) -> Result<()> {
+        instructions::bet::cancel_bet(ctx)
+    }
+
+    pub fn withdraw_bankroll(ctx: Context<WithdrawBankroll>) -> Result<()> {
+        instructions::bet::withdraw_bankroll(ctx)
+    }
+
+    pub fn claim_refund(ctx: Context<ClaimRefund>) -> Result<()> {
+        instructions::bet::claim_refund(ctx)
+    }
 }
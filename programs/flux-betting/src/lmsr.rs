@@ -0,0 +1,443 @@
+use anchor_lang::prelude::*;
+use crate::errors::FluxError;
+
+// Logarithmic market scoring rule priced in unsigned fixed point with 12 decimals.
+// Outstanding shares are q, the liquidity parameter is b and the cost of the book is
+// C(q) = b ln(sum e^(q_j / b)); each winning share pays one token.
+
+pub const ONE: u128 = 1_000_000_000_000;
+
+// ln(2) scaled by ONE
+const LN_2: u128 = 693_147_180_560;
+
+// Largest exponent accepted, e^40 ~ 2.35e17 keeps every product inside a u128
+const MAX_EXP: u128 = 40 * ONE;
+
+// e^x for a non-negative fixed point x
+pub fn exp(x: u128) -> Result<u128> {
+    require!(x <= MAX_EXP, FluxError::LmsrOutOfRange);
+    
+    // e^x = 2^k * e^r with r in [0, ln 2)
+    let k = x / LN_2;
+    let r = x - k * LN_2;
+    
+    let mut sum = ONE;
+    let mut term = ONE;
+    let mut n = 1;
+    while term > 0 {
+        term = term * r / (n * ONE);
+        sum += term;
+        n += 1;
+    }
+    
+    Ok(sum << k)
+}
+
+// e^-x for a non-negative fixed point x, flushed to zero past the exp range
+pub fn exp_neg(x: u128) -> Result<u128> {
+    if x > MAX_EXP {
+        return Ok(0);
+    }
+    
+    Ok(ONE * ONE / exp(x)?)
+}
+
+// natural log of a fixed point x >= 1
+pub fn ln(x: u128) -> Result<u128> {
+    require!(x >= ONE, FluxError::LmsrOutOfRange);
+    
+    // x = 2^k * y with y in [1, 2)
+    let k = (x / ONE).ilog2();
+    let y = x >> k;
+    
+    // ln y = 2 atanh(z) with z = (y - 1) / (y + 1) in [0, 1/3)
+    let z = (y - ONE) * ONE / (y + ONE);
+    let z_squared = z * z / ONE;
+    
+    let mut sum = 0;
+    let mut term = z;
+    let mut n = 1;
+    while term > 0 {
+        sum += term / n;
+        term = term * z_squared / ONE;
+        n += 2;
+    }
+    
+    Ok(k as u128 * LN_2 + 2 * sum)
+}
+
+// e^((from - to) / b), whichever side of zero the exponent falls
+fn exp_diff(from: u64, to: u64, liquidity: u64) -> Result<u128> {
+    if from >= to {
+        exp(((from - to) as u128) * ONE / liquidity as u128)
+    } else {
+        exp_neg(((to - from) as u128) * ONE / liquidity as u128)
+    }
+}
+
+// sum of e^((q_j - q_max) / b), in [1, n] so it is safe to take the log of
+fn normalized_sum(shares: &[u64], liquidity: u64) -> Result<(u64, u128)> {
+    let max = shares.iter().copied().max().unwrap_or(0);
+    
+    let mut sum: u128 = 0;
+    for &q in shares {
+        sum += exp_diff(q, max, liquidity)?;
+    }
+    
+    Ok((max, sum))
+}
+
+// cost of the book, C(q) = q_max + b ln(sum e^((q_j - q_max) / b)), rounded up
+pub fn cost(shares: &[u64], liquidity: u64) -> Result<u64> {
+    require!(liquidity > 0, FluxError::LiquidityRequired);
+    
+    let (max, sum) = normalized_sum(shares, liquidity)?;
+    let log_term = (liquidity as u128 * ln(sum)?).div_ceil(ONE);
+    
+    u64::try_from(max as u128 + log_term).map_err(|_| error!(FluxError::LmsrOutOfRange))
+}
+
+// most the house can lose on a fresh book, b ln n, rounded up
+pub fn max_loss(liquidity: u64, option_count: usize) -> Result<u64> {
+    cost(&vec![0; option_count], liquidity)
+}
+
+// shares of an option that `amount` buys, the d solving C(q + d e_i) - C(q) = amount,
+// normalized against the largest position so an option far behind it stays purchasable,
+// d = q_max - q_i + b ln(e^((q_i - q_max) / b) + S (e^(amount / b) - 1)) with S from
+// normalized_sum, rounded down
+pub fn shares_for_cost(
+    shares: &[u64],
+    liquidity: u64,
+    option_index: usize,
+    amount: u64,
+) -> Result<u64> {
+    require!(liquidity > 0, FluxError::LiquidityRequired);
+    
+    if amount == 0 {
+        return Ok(0);
+    }
+    
+    let (max, sum) = normalized_sum(shares, liquidity)?;
+    let own = exp_diff(shares[option_index], max, liquidity)?;
+    
+    let growth = exp(amount as u128 * ONE / liquidity as u128)? - ONE;
+    let target = sum
+        .checked_mul(growth)
+        .ok_or(FluxError::LmsrOutOfRange)?
+        / ONE
+        + own;
+    require!(target > 0, FluxError::LmsrOutOfRange);
+    
+    // the option ends up at q_max + b ln(target), below q_max while target is under one
+    let gap = (max - shares[option_index]) as u128;
+    let bought = if target >= ONE {
+        gap + liquidity as u128 * ln(target)? / ONE
+    } else {
+        // ln(target) = -ln(1 / target), rounded so the shares still round down
+        let shortfall = (liquidity as u128 * ln((ONE * ONE).div_ceil(target))?).div_ceil(ONE);
+        gap.saturating_sub(shortfall)
+    };
+    
+    u64::try_from(bought).map_err(|_| error!(FluxError::LmsrOutOfRange))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // e scaled by ONE
+    const E: u128 = 2_718_281_828_459;
+    
+    // marginal price of an option, e^(q_i / b) / sum e^(q_j / b), as a fraction of ONE
+    fn price(shares: &[u64], liquidity: u64, option_index: usize) -> Result<u128> {
+        require!(liquidity > 0, FluxError::LiquidityRequired);
+        
+        let (max, sum) = normalized_sum(shares, liquidity)?;
+        
+        Ok(exp_diff(shares[option_index], max, liquidity)? * ONE / sum)
+    }
+    
+    fn assert_close(actual: u128, expected: u128, tolerance: u128) {
+        let diff = actual.abs_diff(expected);
+        assert!(
+            diff <= tolerance,
+            "{actual} differs from {expected} by {diff}, more than {tolerance}"
+        );
+    }
+    
+    // relative tolerance in parts per trillion
+    fn assert_rel_close(actual: u128, expected: u128, parts: u128) {
+        assert_close(actual, expected, expected / (ONE / parts) + 1);
+    }
+    
+    #[test]
+    fn exp_of_zero_is_one() {
+        assert_eq!(exp(0).unwrap(), ONE);
+    }
+    
+    #[test]
+    fn exp_matches_known_values() {
+        assert_close(exp(ONE).unwrap(), E, 10);
+        assert_close(exp(LN_2).unwrap(), 2 * ONE, 10);
+        assert_close(exp(ONE / 2).unwrap(), 1_648_721_270_700, 10);
+        assert_rel_close(exp(10 * ONE).unwrap(), 22_026_465_794_806_718, 100);
+        assert_rel_close(exp(MAX_EXP).unwrap(), 235_385_266_837_019_985_407_899_910_749, 100);
+    }
+    
+    #[test]
+    fn exp_rejects_exponents_past_the_range() {
+        assert!(exp(MAX_EXP + 1).is_err());
+        assert!(exp(100 * ONE).is_err());
+    }
+    
+    #[test]
+    fn exp_is_monotonic() {
+        let mut previous = 0;
+        for step in 0..=400 {
+            let value = exp(step * ONE / 10).unwrap();
+            assert!(value > previous);
+            previous = value;
+        }
+    }
+    
+    #[test]
+    fn exp_of_a_sum_is_the_product() {
+        for (a, b) in [(ONE, ONE), (ONE / 3, 2 * ONE), (5 * ONE, 7 * ONE / 2)] {
+            let product = exp(a).unwrap() * exp(b).unwrap() / ONE;
+            assert_rel_close(exp(a + b).unwrap(), product, 100);
+        }
+    }
+    
+    #[test]
+    fn exp_neg_inverts_exp() {
+        assert_eq!(exp_neg(0).unwrap(), ONE);
+        assert_close(exp_neg(ONE).unwrap(), 367_879_441_171, 10);
+        assert_close(exp_neg(LN_2).unwrap(), ONE / 2, 10);
+        for step in 0..=10 {
+            let x = step * ONE;
+            let product = exp(x).unwrap() * exp_neg(x).unwrap() / ONE;
+            assert_rel_close(product, ONE, 100_000);
+        }
+    }
+    
+    #[test]
+    fn exp_neg_flushes_to_zero_past_the_range() {
+        assert_eq!(exp_neg(MAX_EXP + 1).unwrap(), 0);
+        assert_eq!(exp_neg(u64::MAX as u128 * ONE).unwrap(), 0);
+    }
+    
+    #[test]
+    fn ln_of_one_is_zero() {
+        assert_eq!(ln(ONE).unwrap(), 0);
+    }
+    
+    #[test]
+    fn ln_matches_known_values() {
+        assert_close(ln(2 * ONE).unwrap(), LN_2, 10);
+        assert_close(ln(E).unwrap(), ONE, 10);
+        assert_close(ln(10 * ONE).unwrap(), 2_302_585_092_994, 10);
+        assert_close(ln(3 * ONE / 2).unwrap(), 405_465_108_108, 10);
+        assert_close(ln(1_000_000 * ONE).unwrap(), 13_815_510_557_964, 100);
+    }
+    
+    #[test]
+    fn ln_rejects_values_below_one() {
+        assert!(ln(ONE - 1).is_err());
+        assert!(ln(0).is_err());
+    }
+    
+    #[test]
+    fn ln_is_monotonic() {
+        let mut previous = 0;
+        for step in 1..=1000 {
+            let value = ln(ONE + step * ONE / 7).unwrap();
+            assert!(value > previous);
+            previous = value;
+        }
+    }
+    
+    #[test]
+    fn ln_inverts_exp() {
+        for step in 0..=400 {
+            let x = step * ONE / 10;
+            assert_close(ln(exp(x).unwrap()).unwrap(), x, 100);
+        }
+    }
+    
+    #[test]
+    fn fresh_book_costs_b_ln_n() {
+        let liquidity = 1_000_000;
+        for option_count in 2..=10 {
+            let expected = liquidity as u128 * ln(option_count * ONE).unwrap();
+            assert_close(
+                max_loss(liquidity, option_count as usize).unwrap() as u128,
+                expected.div_ceil(ONE),
+                1,
+            );
+        }
+    }
+    
+    #[test]
+    fn fresh_book_prices_every_option_equally() {
+        for option_count in 2..=10usize {
+            let shares = vec![0; option_count];
+            for option_index in 0..option_count {
+                assert_close(
+                    price(&shares, 1_000_000, option_index).unwrap(),
+                    ONE / option_count as u128,
+                    1,
+                );
+            }
+        }
+    }
+    
+    #[test]
+    fn prices_sum_to_one() {
+        let books: [&[u64]; 4] = [
+            &[0, 0],
+            &[5_000_000, 1_000_000],
+            &[3_000_000, 0, 7_500_000],
+            &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10],
+        ];
+        for shares in books {
+            let total: u128 = (0..shares.len())
+                .map(|i| price(shares, 2_000_000, i).unwrap())
+                .sum();
+            assert_close(total, ONE, shares.len() as u128);
+        }
+    }
+    
+    #[test]
+    fn price_follows_demand() {
+        let liquidity = 1_000_000;
+        let before = price(&[0, 0], liquidity, 0).unwrap();
+        let after = price(&[2_000_000, 0], liquidity, 0).unwrap();
+        let other = price(&[2_000_000, 0], liquidity, 1).unwrap();
+        assert!(after > before);
+        assert!(other < before);
+        // e^2 / (e^2 + 1)
+        assert_close(after, 880_797_077_977, 10);
+    }
+    
+    #[test]
+    fn price_of_a_lopsided_book_stays_within_bounds() {
+        let shares = [u32::MAX as u64, 0];
+        assert!(price(&shares, 1_000, 0).unwrap() <= ONE);
+        assert_eq!(price(&shares, 1_000, 1).unwrap(), 0);
+    }
+    
+    #[test]
+    fn zero_amount_buys_no_shares() {
+        assert_eq!(shares_for_cost(&[0, 0], 1_000_000, 0, 0).unwrap(), 0);
+        assert_eq!(shares_for_cost(&[4_000_000, 0], 1_000_000, 1, 0).unwrap(), 0);
+    }
+    
+    #[test]
+    fn small_buys_fill_at_the_marginal_price() {
+        let liquidity = 1_000_000_000;
+        let shares = [0, 0];
+        // at 0.5 per share a tiny buy gets twice its amount in shares
+        let bought = shares_for_cost(&shares, liquidity, 0, 1_000).unwrap();
+        assert_close(bought as u128, 2_000, 1);
+    }
+    
+    #[test]
+    fn shares_match_the_cost_difference() {
+        let liquidity = 1_000_000;
+        let books: [&[u64]; 3] = [&[0, 0], &[3_000_000, 500_000], &[0, 250_000, 9_000_000]];
+        for shares in books {
+            for option_index in 0..shares.len() {
+                for amount in [1_000, 100_000, 1_000_000, 5_000_000] {
+                    let bought = shares_for_cost(shares, liquidity, option_index, amount).unwrap();
+                    let mut after = shares.to_vec();
+                    after[option_index] += bought;
+                    let paid = cost(&after, liquidity).unwrap() - cost(shares, liquidity).unwrap();
+                    // shares round down, so the book never charges less than it should
+                    assert!(paid <= amount + 1, "paid {paid} for {amount}");
+                    assert_close(paid as u128, amount as u128, 3);
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn more_money_buys_more_shares() {
+        let liquidity = 1_000_000;
+        let mut previous = 0;
+        for amount in (100_000..=10_000_000).step_by(100_000) {
+            let bought = shares_for_cost(&[1_000_000, 0], liquidity, 1, amount).unwrap();
+            assert!(bought > previous);
+            previous = bought;
+        }
+    }
+    
+    #[test]
+    fn shares_pay_at_least_their_cost() {
+        let liquidity = 1_000_000;
+        for amount in [1_000, 50_000, 1_000_000, 20_000_000] {
+            assert!(shares_for_cost(&[0, 0], liquidity, 0, amount).unwrap() >= amount);
+        }
+    }
+    
+    #[test]
+    fn cheap_options_buy_more_shares() {
+        let liquidity = 1_000_000;
+        let shares = [4_000_000, 0];
+        let favourite = shares_for_cost(&shares, liquidity, 0, 100_000).unwrap();
+        let longshot = shares_for_cost(&shares, liquidity, 1, 100_000).unwrap();
+        assert!(longshot > favourite);
+    }
+    
+    #[test]
+    fn house_loss_is_bounded_by_b_ln_n() {
+        let liquidity = 1_000_000;
+        let mut shares = vec![0u64; 3];
+        let mut collected = 0u64;
+        let trades = [
+            (0, 700_000),
+            (1, 2_000_000),
+            (0, 1_500_000),
+            (2, 300_000),
+            (0, 4_000_000),
+            (1, 10_000),
+            (0, 9_000_000),
+        ];
+        for (option_index, amount) in trades {
+            let bought = shares_for_cost(&shares, liquidity, option_index, amount).unwrap();
+            shares[option_index] += bought;
+            collected += amount;
+            
+            let bound = collected + max_loss(liquidity, shares.len()).unwrap();
+            assert!(shares.iter().all(|&q| q <= bound));
+        }
+    }
+    
+    #[test]
+    fn buys_past_the_range_are_rejected() {
+        // spending 50b in one go needs e^50
+        assert!(shares_for_cost(&[0, 0], 1_000, 0, 50_000).is_err());
+    }
+    
+    #[test]
+    fn options_far_behind_stay_purchasable() {
+        let liquidity = 1_000;
+        // the other side sits 50b ahead, past the exp range
+        let shares = [50_000, 0];
+        let bought = shares_for_cost(&shares, liquidity, 1, 10).unwrap();
+        // ln(e^0.01 - 1) ~ -4.6, so the longshot ends up ~4.6b behind the favourite
+        assert_close(bought as u128, 45_400, 10);
+        
+        let after = [50_000, bought];
+        let paid = cost(&after, liquidity).unwrap() - cost(&shares, liquidity).unwrap();
+        assert!(paid <= 10 + 1, "paid {paid} for 10");
+        assert_close(paid as u128, 10, 1);
+    }
+    
+    #[test]
+    fn zero_liquidity_is_rejected() {
+        assert!(cost(&[0, 0], 0).is_err());
+        assert!(price(&[0, 0], 0, 0).is_err());
+        assert!(shares_for_cost(&[0, 0], 0, 0, 1).is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use anchor_lang::prelude::*;
+use crate::errors::FluxError;
+
+// Account discriminator of the Pyth receiver's PriceUpdateV2 account
+pub const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+
+// Unread fields are kept so the layout matches the account data
+#[allow(dead_code)]
+#[derive(AnchorDeserialize)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+#[allow(dead_code)]
+#[derive(AnchorDeserialize)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
+
+// Mirrors the layout of the Pyth receiver's PriceUpdateV2 account
+#[allow(dead_code)]
+#[derive(AnchorDeserialize)]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub price_message: PriceFeedMessage,
+    pub posted_slot: u64,
+}
+
+// load a fully verified price update owned by the platform's oracle program
+pub fn load_price_update(
+    price_feed: &AccountInfo,
+    oracle_program: &Pubkey,
+) -> Result<PriceUpdateV2> {
+    require_keys_eq!(*price_feed.owner, *oracle_program, FluxError::InvalidOracleAccount);
+    
+    let data = price_feed.try_borrow_data()?;
+    require!(
+        data.len() > 8 && data[..8] == PRICE_UPDATE_V2_DISCRIMINATOR,
+        FluxError::InvalidOracleAccount
+    );
+    
+    let price_update = PriceUpdateV2::deserialize(&mut &data[8..])
+        .map_err(|_| error!(FluxError::InvalidOracleAccount))?;
+    
+    require!(
+        matches!(price_update.verification_level, VerificationLevel::Full),
+        FluxError::InvalidOracleAccount
+    );
+    
+    Ok(price_update)
+}
@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 use crate::errors::FluxError;
+use crate::utils::refunds_stakes;
 
 #[account]
 pub struct Platform {
@@ -9,6 +10,10 @@ pub struct Platform {
     pub total_bets: u64,
     pub total_users: u64,
     pub total_groups: u64,
+    pub oracle_program: Pubkey, // Owner of the price accounts used by oracle resolved bets
+    pub arbiter: Pubkey, // Settles disputed resolutions alongside the admin
+    pub pending_admin: Option<Pubkey>, // Proposed admin, takes over once they accept
+    pub paused: bool, // Blocks joining groups, new bets and wagers, never payouts
     pub bump: u8,
 }
 
@@ -17,9 +22,11 @@ pub struct Group {
     pub name: String,
     pub description: String,
     pub admin: Pubkey,
-    pub members: Vec<Pubkey>,
-    pub active_bets: Vec<Pubkey>,
-    pub past_bets: Vec<Pubkey>,
+    pub pending_admin: Option<Pubkey>, // Proposed admin, takes over once they accept
+    pub visibility: GroupVisibility,
+    pub member_count: u64, // Members are GroupMember accounts
+    pub active_bet_count: u64, // Bets are Bet accounts keyed by group
+    pub past_bet_count: u64,
     pub created_at: i64,
     pub bump: u8,
 }
@@ -28,18 +35,85 @@ pub struct Group {
 pub struct UserProfile {
     pub user: Pubkey,
     pub groups: Vec<Pubkey>,
-    pub active_bets: Vec<Pubkey>,
-    pub past_bets: Vec<Pubkey>,
+    pub active_bet_count: u64, // History entries are BetRecord accounts
+    pub past_bet_count: u64,
     pub total_winnings: u64,
     pub total_losses: u64,
     pub bump: u8,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GroupVisibility {
+    Open,              // Anyone with the group address can join
+    InviteOnly,        // Joining consumes a use of an Invite
+    ApprovalRequired,  // Joining needs a JoinRequest approved by the admin
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum GroupRole {
+    Member,     // Joins the group and places bets
+    Creator,    // Also creates bets and resolves their own
+    Moderator,  // Also resolves any bet in the group
+    Admin,      // Held by the group admin, manages roles
+}
+
+impl GroupRole {
+    pub fn can_create_bets(&self) -> bool {
+        !matches!(self, GroupRole::Member)
+    }
+    
+    // creators resolve their own bets, moderators and the admin any bet
+    pub fn can_resolve(&self, is_bet_creator: bool) -> bool {
+        match self {
+            GroupRole::Member => false,
+            GroupRole::Creator => is_bet_creator,
+            GroupRole::Moderator | GroupRole::Admin => true,
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PayoutMode {
+    FixedOdds,   // Winners are paid amount * odds / 100
+    Parimutuel,  // Winners split the pool pro rata by their stake
+    Lmsr,        // Stakes buy outcome shares from a market maker, each winning share pays 1
+    DynamicOdds, // Odds reprice from the pool after each wager, winners are paid the odds they took
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SettlementAsset {
+    Token,      // Stakes are SPL tokens held in the vault for the bet's mint
+    NativeSol,  // Stakes are lamports held in the bet's escrow
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PriceRange {
+    pub lower: u64, // Inclusive
+    pub upper: u64, // Exclusive, u64::MAX for an open-ended range
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct OracleConfig {
+    pub price_feed: Pubkey,
+    pub feed_id: [u8; 32],
+    pub max_staleness: i64, // Seconds after end_time the closing price may be published
+    pub max_confidence_bps: u16, // Max confidence interval as basis points of the price
+    pub exponent: i32, // Exponent the price ranges are quoted in
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CashOutCurve {
+    pub max_return_bps: u16, // Share of the position's value returned right after creation
+    pub min_return_bps: u16, // Share returned just before end_time
+}
+
 #[account]
 pub struct Bet {
     pub id: String,
     pub group: Pubkey,
     pub creator: Pubkey,
+    pub mint: Pubkey, // Token staked and paid out, default for native SOL bets
+    pub settlement_asset: SettlementAsset,
     pub coin: String,
     pub description: String,
     pub options: Vec<String>,
@@ -47,19 +121,81 @@ pub struct Bet {
     pub min_bet_amount: u64,
     pub total_pool: u64,
     pub bets_per_option: Vec<u64>,
+    pub liabilities: Vec<u64>, // Gross payout owed per option should it win, outstanding shares for LMSR
+    pub bankroll: u64, // House deposit backing payouts, kept apart from total_pool
+    pub house: Option<Pubkey>, // Funded the bankroll and takes what it leaves after settlement
+    pub bankroll_withdrawn: bool,
+    pub liquidity: u64, // LMSR liquidity parameter b, zero for other payout modes
+    pub overround_bps: u16, // Margin built into dynamic odds, zero for other payout modes
+    pub fee_percentage: u16, // Platform fee in basis points when the bet was created
     pub created_at: i64,
     pub end_time: i64,
     pub resolved: bool,
+    pub cancelled: bool,
     pub winning_option: Option<u8>,
     pub actual_price: Option<u64>,
+    pub payout_mode: PayoutMode,
+    pub oracle: Option<OracleConfig>,
+    pub price_ranges: Vec<PriceRange>, // One [lower, upper) band per option, empty for free-form options
+    pub challenge_period: i64, // Seconds a proposed outcome can be disputed
+    pub dispute_bond: u64, // Bond posted by the proposer and by a disputer
+    pub challenge_deadline: Option<i64>, // Set once an outcome is proposed
+    pub proposer: Option<Pubkey>, // Posted the proposal bond and recovers it if upheld
+    pub disputer: Option<Pubkey>,
+    pub cash_out: Option<CashOutCurve>, // None disables early cash-out
+    pub bump: u8,
+}
+
+// Membership of one user in one group
+#[account]
+pub struct GroupMember {
+    pub group: Pubkey,
+    pub user: Pubkey,
+    pub joined_at: i64,
+    pub open_positions: u64, // Unsettled UserBet positions, must be zero to leave
+    pub role: GroupRole,
+    pub bump: u8,
+}
+
+// Invite code for an invite-only group, stored as a hash of the code
+#[account]
+pub struct Invite {
+    pub group: Pubkey,
+    pub code_hash: [u8; 32], // SHA-256 of the invite code
+    pub expires_at: i64,
+    pub max_uses: u32,
+    pub uses: u32,
+    pub bump: u8,
+}
+
+// Request to join an approval-required group
+#[account]
+pub struct JoinRequest {
+    pub group: Pubkey,
+    pub user: Pubkey,
+    pub requested_at: i64,
+    pub approved: bool,
+    pub bump: u8,
+}
+
+// History entry for one user and a bet they staked on
+#[account]
+pub struct BetRecord {
+    pub user: Pubkey,
+    pub bet: Pubkey,
+    pub open_positions: u64, // UserBet positions on the bet not yet claimed, refunded, closed or cashed out
+    pub settled: bool, // Moved to past bets once no open positions are left
     pub bump: u8,
 }
 
+// One position per user, bet and option
 #[account]
 pub struct UserBet {
     pub user: Pubkey,
     pub bet: Pubkey,
     pub amount: u64,
+    pub shares: u64, // LMSR outcome shares held, amount is what they cost
+    pub locked_odds: u16, // Stake-weighted odds taken on dynamic odds bets
     pub option_index: u8,
     pub claimed: bool,
     pub winnings: Option<u64>,
@@ -67,12 +203,22 @@ pub struct UserBet {
 }
 
 
+// Platform fees retained from payouts, one ledger per mint
+#[account]
+pub struct FeeLedger {
+    pub mint: Pubkey,
+    pub accrued_fees: u64, // Retained in the treasury and not yet withdrawn
+    pub total_collected: u64,
+    pub total_withdrawn: u64,
+    pub bump: u8,
+}
+
 #[derive(Accounts)]
 pub struct InitializePlatform<'info> {
     #[account(
         init, 
         payer = admin, 
-        space = 8 + 32 + 2 + 32 + 8 + 8 + 8 + 1,
+        space = 8 + 32 + 2 + 32 + 8 + 8 + 8 + 32 + 32 + 33 + 1 + 1,
         seeds = [b"platform"],
         bump
     )]
@@ -87,13 +233,80 @@ pub struct InitializePlatform<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct InitVault<'info> {
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    pub mint: InterfaceAccount<'info, anchor_spl::token_interface::Mint>,
+    
+    #[account(
+        init,
+        payer = payer,
+        seeds = [b"vault", mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = platform,
+        token::token_program = token_program
+    )]
+    pub vault: InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub token_program: Interface<'info, anchor_spl::token_interface::TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetArbiter<'info> {
+    #[account(
+        mut,
+        seeds = [b"platform"],
+        bump = platform.bump,
+        constraint = platform.admin == admin.key() @ FluxError::UnauthorizedPlatformAdmin
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePlatform<'info> {
+    #[account(
+        mut,
+        seeds = [b"platform"],
+        bump = platform.bump,
+        constraint = platform.admin == admin.key() @ FluxError::UnauthorizedPlatformAdmin
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptPlatformAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"platform"],
+        bump = platform.bump,
+        constraint = platform.pending_admin == Some(new_admin.key()) @ FluxError::NotPendingPlatformAdmin
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    pub new_admin: Signer<'info>,
+}
+
 #[derive(Accounts)]
 #[instruction(name: String, description: String)]
 pub struct CreateGroup<'info> {
     #[account(
         init,
         payer = admin,
-        space = 8 + 4 + name.len() + 4 + description.len() + 32 + 4 + (32 * 10) + 4 + (32 * 50) + 4 + (32 * 50) + 8 + 1,
+        space = 8 + 4 + name.len() + 4 + description.len() + 32 + 33 + 1 + 8 + 8 + 8 + 8 + 1,
         seeds = [b"group", admin.key().as_ref(), name.as_bytes()],
         bump
     )]
@@ -109,10 +322,19 @@ pub struct CreateGroup<'info> {
     )]
     pub platform: Account<'info, Platform>,
     
+    #[account(
+        init,
+        payer = admin,
+        space = 8 + 32 + 32 + 8 + 8 + 1 + 1,
+        seeds = [b"group_member", group.key().as_ref(), admin.key().as_ref()],
+        bump
+    )]
+    pub group_member: Account<'info, GroupMember>,
+    
     #[account(
         init_if_needed,
         payer = admin,
-        space = 8 + 32 + 4 + (32 * 10) + 4 + (32 * 50) + 4 + (32 * 50) + 8 + 8 + 1,
+        space = 8 + 32 + 4 + (32 * 10) + 8 + 8 + 8 + 8 + 1,
         seeds = [b"user_profile", admin.key().as_ref()],
         bump
     )]
@@ -129,192 +351,1262 @@ pub struct JoinGroup<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
     
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump,
+        constraint = !platform.paused @ FluxError::PlatformPaused
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + 32 + 32 + 8 + 8 + 1 + 1,
+        seeds = [b"group_member", group.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub group_member: Account<'info, GroupMember>,
+    
     #[account(
         init_if_needed,
         payer = user,
-        space = 8 + 32 + 4 + (32 * 10) + 4 + (32 * 50) + 4 + (32 * 50) + 8 + 8 + 1,
+        space = 8 + 32 + 4 + (32 * 10) + 8 + 8 + 8 + 8 + 1,
         seeds = [b"user_profile", user.key().as_ref()],
         bump
     )]
     pub user_profile: Account<'info, UserProfile>,
     
+    #[account(
+        mut,
+        constraint = invite.group == group.key() @ FluxError::InvalidInvite
+    )]
+    pub invite: Option<Account<'info, Invite>>,
+    
+    #[account(
+        mut,
+        seeds = [b"join_request", group.key().as_ref(), user.key().as_ref()],
+        bump = join_request.bump
+    )]
+    pub join_request: Option<Account<'info, JoinRequest>>,
+    
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(bet_id: String, coin: String, description: String, options: Vec<String>, odds: Vec<u16>, end_time: i64, min_bet_amount: u64)]
-pub struct CreateBet<'info> {
+pub struct LeaveGroup<'info> {
     #[account(
-        init,
-        payer = creator,
-        space = 8 + // discriminator
-            4 + bet_id.len() + // id (String)
-            32 + // group (Pubkey)
-            32 + // creator (Pubkey)
-            4 + coin.len() + // coin (String)
-            4 + description.len() + // description (String)
-            4 + options.iter().map(|s| 4 + s.len()).sum::<usize>() + // options (Vec<String>)
-            4 + (odds.len() * 2) + // odds (Vec<u16>)
-            8 + // min_bet_amount (u64)
-            8 + // total_pool (u64)
-            4 + (options.len() * 8) + // bets_per_option (Vec<u64>)
-            8 + // created_at (i64)
-            8 + // end_time (i64)
-            1 + // resolved (bool)
-            2 + // Option<u8> for winning_option (1 for is_some + 1 for u8)
-            9 + // Option<u64> for actual_price (1 for is_some + 8 for u64)
-            1, // bump (u8)
-        seeds = [b"bet", group.key().as_ref(), bet_id.as_bytes()],
-        bump
+        mut,
+        constraint = group.admin != user.key() @ FluxError::GroupAdminCannotLeave
     )]
-    pub bet: Account<'info, Bet>,
+    pub group: Account<'info, Group>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"group_member", group.key().as_ref(), user.key().as_ref()],
+        bump = group_member.bump,
+        constraint = group_member.open_positions == 0 @ FluxError::MemberHasOpenPositions
+    )]
+    pub group_member: Account<'info, GroupMember>,
     
     #[account(
         mut,
-        constraint = group.admin == creator.key() @ FluxError::UnauthorizedBetCreator
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump,
+        constraint = user_profile.user == user.key()
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveMember<'info> {
+    #[account(
+        mut,
+        constraint = group.admin == admin.key() @ FluxError::UnauthorizedGroupAdmin,
+        constraint = group.admin != member.key() @ FluxError::GroupAdminCannotLeave
     )]
     pub group: Account<'info, Group>,
     
+    pub admin: Signer<'info>,
+    
+    /// CHECK: The removed member, refunded the rent of their membership account
     #[account(mut)]
-    pub creator: Signer<'info>,
+    pub member: UncheckedAccount<'info>,
     
     #[account(
         mut,
-        seeds = [b"platform"],
-        bump = platform.bump
+        close = member,
+        seeds = [b"group_member", group.key().as_ref(), member.key().as_ref()],
+        bump = group_member.bump,
+        constraint = group_member.open_positions == 0 @ FluxError::MemberHasOpenPositions
     )]
-    pub platform: Account<'info, Platform>,
+    pub group_member: Account<'info, GroupMember>,
     
     #[account(
         mut,
-        seeds = [b"user_profile", creator.key().as_ref()],
+        seeds = [b"user_profile", member.key().as_ref()],
         bump = user_profile.bump,
-        constraint = user_profile.user == creator.key()
+        constraint = user_profile.user == member.key()
     )]
     pub user_profile: Account<'info, UserProfile>,
-    
-    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(amount: u64, option_index: u8)]
-pub struct PlaceBet<'info> {
+pub struct SetGroupVisibility<'info> {
     #[account(
         mut,
-        constraint = !bet.resolved @ FluxError::BetAlreadyResolved,
-        constraint = Clock::get().unwrap().unix_timestamp < bet.end_time @ FluxError::BetPeriodEnded,
-        constraint = option_index < bet.options.len() as u8 @ FluxError::InvalidOptionIndex,
-        constraint = amount >= bet.min_bet_amount @ FluxError::BetAmountBelowMinimum
+        constraint = group.admin == admin.key() @ FluxError::UnauthorizedGroupAdmin
     )]
-    pub bet: Account<'info, Bet>,
+    pub group: Account<'info, Group>,
     
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(code_hash: [u8; 32])]
+pub struct CreateInvite<'info> {
     #[account(
-        mut,
-        constraint = group.members.contains(&user.key()) @ FluxError::NotGroupMember
+        constraint = group.admin == admin.key() @ FluxError::UnauthorizedGroupAdmin
     )]
     pub group: Account<'info, Group>,
     
     #[account(mut)]
-    pub user: Signer<'info>,
+    pub admin: Signer<'info>,
     
     #[account(
         init,
-        payer = user,
-        space = 8 + 32 + 32 + 8 + 1 + 1 + 8 + 1,
-        seeds = [b"user_bet", bet.key().as_ref(), user.key().as_ref()],
+        payer = admin,
+        space = 8 + 32 + 32 + 8 + 4 + 4 + 1,
+        seeds = [b"invite", group.key().as_ref(), code_hash.as_ref()],
         bump
     )]
-    pub user_bet: Account<'info, UserBet>,
+    pub invite: Account<'info, Invite>,
     
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeInvite<'info> {
     #[account(
-        mut,
-        seeds = [b"user_profile", user.key().as_ref()],
-        bump,
-        constraint = user_profile.user == user.key()
+        constraint = group.admin == admin.key() @ FluxError::UnauthorizedGroupAdmin
     )]
-    pub user_profile: Account<'info, UserProfile>,
+    pub group: Account<'info, Group>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
     
     #[account(
         mut,
-        seeds = [b"platform"],
-        bump = platform.bump
+        close = admin,
+        seeds = [b"invite", group.key().as_ref(), invite.code_hash.as_ref()],
+        bump = invite.bump
     )]
-    pub platform: Account<'info, Platform>,
-    
-    #[account(mut)]
-    pub user_token_account: Account<'info, anchor_spl::token::TokenAccount>,
-    
-    #[account(mut)]
-    pub treasury_token_account: Account<'info, anchor_spl::token::TokenAccount>,
-    
-    pub token_program: Program<'info, anchor_spl::token::Token>,
-    pub system_program: Program<'info, System>,
+    pub invite: Account<'info, Invite>,
 }
 
 #[derive(Accounts)]
-#[instruction(winning_option: u8, actual_price: u64)]
-pub struct ResolveBet<'info> {
+pub struct RequestToJoin<'info> {
     #[account(
-        mut,
-        constraint = !bet.resolved @ FluxError::BetAlreadyResolved,
-        constraint = winning_option < bet.options.len() as u8 @ FluxError::InvalidOptionIndex,
-        constraint = bet.creator == creator.key() @ FluxError::UnauthorizedResolver
+        constraint = group.visibility == GroupVisibility::ApprovalRequired @ FluxError::ApprovalNotRequired
     )]
-    pub bet: Account<'info, Bet>,
+    pub group: Account<'info, Group>,
     
     #[account(mut)]
-    pub creator: Signer<'info>,
+    pub user: Signer<'info>,
     
-    #[account(mut)]
-    pub group: Account<'info, Group>,
+    #[account(
+        init,
+        payer = user,
+        space = 8 + 32 + 32 + 8 + 1 + 1,
+        seeds = [b"join_request", group.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub join_request: Account<'info, JoinRequest>,
     
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimWinnings<'info> {
+pub struct ApproveJoinRequest<'info> {
     #[account(
-        mut,
-        constraint = bet.resolved @ FluxError::BetNotResolved,
-        seeds = [b"bet", bet.group.as_ref(), bet.id.as_bytes()],
-        bump = bet.bump
+        constraint = group.admin == admin.key() @ FluxError::UnauthorizedGroupAdmin
     )]
-    pub bet: Account<'info, Bet>,
+    pub group: Account<'info, Group>,
+    
+    pub admin: Signer<'info>,
     
     #[account(
         mut,
-        seeds = [b"user_bet", bet.key().as_ref(), user.key().as_ref()],
-        bump = user_bet.bump,
-        constraint = user_bet.user == user.key(),
-        constraint = !user_bet.claimed @ FluxError::NoWinningsToClaim,
-        constraint = Some(user_bet.option_index) == bet.winning_option @ FluxError::NoWinningsToClaim
+        seeds = [b"join_request", group.key().as_ref(), join_request.user.as_ref()],
+        bump = join_request.bump
     )]
-    pub user_bet: Account<'info, UserBet>,
+    pub join_request: Account<'info, JoinRequest>,
+}
+
+#[derive(Accounts)]
+pub struct RejectJoinRequest<'info> {
+    #[account(
+        constraint = group.admin == admin.key() @ FluxError::UnauthorizedGroupAdmin
+    )]
+    pub group: Account<'info, Group>,
     
-    #[account(mut)]
-    pub user: Signer<'info>,
+    pub admin: Signer<'info>,
     
+    /// CHECK: The requesting user, refunded the rent of their request
     #[account(
         mut,
-        seeds = [b"user_profile", user.key().as_ref()],
-        bump = user_profile.bump,
-        constraint = user_profile.user == user.key()
+        address = join_request.user @ FluxError::InvalidRecipient
     )]
-    pub user_profile: Account<'info, UserProfile>,
+    pub user: UncheckedAccount<'info>,
     
     #[account(
         mut,
-        seeds = [b"platform"],
-        bump = platform.bump
+        close = user,
+        seeds = [b"join_request", group.key().as_ref(), join_request.user.as_ref()],
+        bump = join_request.bump
     )]
-    pub platform: Account<'info, Platform>,
+    pub join_request: Account<'info, JoinRequest>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRole<'info> {
+    #[account(
+        constraint = group.admin == admin.key() @ FluxError::UnauthorizedGroupAdmin
+    )]
+    pub group: Account<'info, Group>,
+    
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"group_member", group.key().as_ref(), group_member.user.as_ref()],
+        bump = group_member.bump,
+        constraint = group_member.user != group.admin @ FluxError::GroupAdminRoleLocked
+    )]
+    pub group_member: Account<'info, GroupMember>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeGroupAdmin<'info> {
+    #[account(
+        mut,
+        constraint = group.admin == admin.key() @ FluxError::UnauthorizedGroupAdmin
+    )]
+    pub group: Account<'info, Group>,
+    
+    pub admin: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptGroupAdmin<'info> {
+    #[account(
+        mut,
+        constraint = group.pending_admin == Some(new_admin.key()) @ FluxError::NotPendingGroupAdmin
+    )]
+    pub group: Account<'info, Group>,
+    
+    pub new_admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"group_member", group.key().as_ref(), new_admin.key().as_ref()],
+        bump = new_admin_member.bump
+    )]
+    pub new_admin_member: Account<'info, GroupMember>,
+    
+    #[account(
+        mut,
+        seeds = [b"group_member", group.key().as_ref(), group.admin.as_ref()],
+        bump = previous_admin_member.bump
+    )]
+    pub previous_admin_member: Account<'info, GroupMember>,
+}
+
+#[derive(Accounts)]
+pub struct CloseGroup<'info> {
+    #[account(
+        mut,
+        close = admin,
+        constraint = group.admin == admin.key() @ FluxError::UnauthorizedGroupAdmin,
+        constraint = group.active_bet_count == 0 @ FluxError::GroupHasActiveBets,
+        constraint = group.member_count == 1 @ FluxError::GroupHasMembers
+    )]
+    pub group: Account<'info, Group>,
+    
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        close = admin,
+        seeds = [b"group_member", group.key().as_ref(), admin.key().as_ref()],
+        bump = group_member.bump,
+        constraint = group_member.open_positions == 0 @ FluxError::MemberHasOpenPositions
+    )]
+    pub group_member: Account<'info, GroupMember>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_profile", admin.key().as_ref()],
+        bump = user_profile.bump,
+        constraint = user_profile.user == admin.key()
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+}
+
+#[derive(Accounts)]
+#[instruction(bet_id: String, coin: String, description: String, options: Vec<String>, odds: Option<Vec<u16>>, end_time: i64, min_bet_amount: u64, payout_mode: PayoutMode, oracle: Option<OracleConfig>, price_ranges: Option<Vec<PriceRange>>, challenge_period: i64, dispute_bond: u64, cash_out: Option<CashOutCurve>, settlement_asset: SettlementAsset, liquidity: Option<u64>, overround_bps: Option<u16>)]
+pub struct CreateBet<'info> {
+    #[account(
+        init,
+        payer = creator,
+        space = 8 + // discriminator
+            4 + bet_id.len() + // id (String)
+            32 + // group (Pubkey)
+            32 + // creator (Pubkey)
+            32 + // mint (Pubkey)
+            1 + // settlement_asset (SettlementAsset)
+            4 + coin.len() + // coin (String)
+            4 + description.len() + // description (String)
+            4 + options.iter().map(|s| 4 + s.len()).sum::<usize>() + // options (Vec<String>)
+            4 + (odds.as_ref().map_or(0, |o| o.len()) * 2) + // odds (Vec<u16>)
+            8 + // min_bet_amount (u64)
+            8 + // total_pool (u64)
+            4 + (options.len() * 8) + // bets_per_option (Vec<u64>)
+            4 + (options.len() * 8) + // liabilities (Vec<u64>)
+            8 + // bankroll (u64)
+            33 + // Option<Pubkey> for house
+            1 + // bankroll_withdrawn (bool)
+            8 + // liquidity (u64)
+            2 + // overround_bps (u16)
+            2 + // fee_percentage (u16)
+            8 + // created_at (i64)
+            8 + // end_time (i64)
+            1 + // resolved (bool)
+            1 + // cancelled (bool)
+            2 + // Option<u8> for winning_option (1 for is_some + 1 for u8)
+            9 + // Option<u64> for actual_price (1 for is_some + 8 for u64)
+            1 + // payout_mode (PayoutMode)
+            1 + oracle.as_ref().map_or(0, |_| 32 + 32 + 8 + 2 + 4) + // oracle (Option<OracleConfig>)
+            4 + (price_ranges.as_ref().map_or(0, |r| r.len()) * 16) + // price_ranges (Vec<PriceRange>)
+            8 + // challenge_period (i64)
+            8 + // dispute_bond (u64)
+            9 + // Option<i64> for challenge_deadline
+            33 + // Option<Pubkey> for proposer
+            33 + // Option<Pubkey> for disputer
+            5 + // Option<CashOutCurve> for cash_out
+            1, // bump (u8)
+        seeds = [b"bet", group.key().as_ref(), bet_id.as_bytes()],
+        bump
+    )]
+    pub bet: Account<'info, Bet>,
+    
+    #[account(mut)]
+    pub group: Account<'info, Group>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+    
+    #[account(
+        seeds = [b"group_member", group.key().as_ref(), creator.key().as_ref()],
+        bump = group_member.bump,
+        constraint = group_member.role.can_create_bets() @ FluxError::UnauthorizedBetCreator
+    )]
+    pub group_member: Account<'info, GroupMember>,
+    
+    #[account(
+        mut,
+        seeds = [b"platform"],
+        bump = platform.bump,
+        constraint = !platform.paused @ FluxError::PlatformPaused
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    pub mint: Option<InterfaceAccount<'info, anchor_spl::token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", bet.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FundBet<'info> {
+    #[account(
+        mut,
+        constraint = !bet.resolved @ FluxError::BetAlreadyResolved,
+        constraint = !bet.cancelled @ FluxError::BetCancelled,
+        constraint = bet.challenge_deadline.is_none() @ FluxError::ResolutionAlreadyProposed,
+        constraint = bet.payout_mode != PayoutMode::Parimutuel @ FluxError::BankrollNotSupported,
+        constraint = match bet.house {
+            None => true,
+            Some(house) => house == funder.key(),
+        } @ FluxError::UnauthorizedFunder
+    )]
+    pub bet: Account<'info, Bet>,
+    
+    #[account(mut)]
+    pub funder: Signer<'info>,
+    
+    // the bet creator or the group admin, on the group's behalf, can back the bet
+    #[account(
+        constraint = group.key() == bet.group,
+        constraint = bet.creator == funder.key() || group.admin == funder.key() @ FluxError::UnauthorizedFunder
+    )]
+    pub group: Account<'info, Group>,
+    
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    #[account(
+        mut,
+        constraint = funder_token_account.owner == funder.key() @ FluxError::InvalidTokenAccountOwner,
+        constraint = funder_token_account.mint == bet.mint @ FluxError::InvalidMint
+    )]
+    pub funder_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", bet.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(address = bet.mint @ FluxError::InvalidMint)]
+    pub mint: Option<InterfaceAccount<'info, anchor_spl::token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", bet.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    
+    pub token_program: Option<Interface<'info, anchor_spl::token_interface::TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(amount: u64, option_index: u8)]
+pub struct PlaceBet<'info> {
+    #[account(
+        mut,
+        constraint = !bet.resolved @ FluxError::BetAlreadyResolved,
+        constraint = !bet.cancelled @ FluxError::BetCancelled,
+        constraint = Clock::get().unwrap().unix_timestamp < bet.end_time @ FluxError::BetPeriodEnded,
+        constraint = bet.challenge_deadline.is_none() @ FluxError::ResolutionAlreadyProposed,
+        constraint = option_index < bet.options.len() as u8 @ FluxError::InvalidOptionIndex,
+        constraint = amount >= bet.min_bet_amount @ FluxError::BetAmountBelowMinimum
+    )]
+    pub bet: Account<'info, Bet>,
+    
+    #[account(
+        mut,
+        constraint = group.key() == bet.group
+    )]
+    pub group: Account<'info, Group>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"group_member", group.key().as_ref(), user.key().as_ref()],
+        bump = group_member.bump,
+        constraint = group_member.user == user.key() @ FluxError::NotGroupMember
+    )]
+    pub group_member: Account<'info, GroupMember>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + 32 + 32 + 8 + 8 + 2 + 1 + 1 + 9 + 1,
+        seeds = [b"user_bet", bet.key().as_ref(), user.key().as_ref(), &[option_index]],
+        bump
+    )]
+    pub user_bet: Account<'info, UserBet>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump,
+        constraint = user_profile.user == user.key()
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + 32 + 32 + 8 + 1 + 1,
+        seeds = [b"bet_record", bet.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub bet_record: Account<'info, BetRecord>,
+    
+    #[account(
+        mut,
+        seeds = [b"platform"],
+        bump = platform.bump,
+        constraint = !platform.paused @ FluxError::PlatformPaused
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ FluxError::InvalidTokenAccountOwner,
+        constraint = user_token_account.mint == bet.mint @ FluxError::InvalidMint
+    )]
+    pub user_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", bet.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(address = bet.mint @ FluxError::InvalidMint)]
+    pub mint: Option<InterfaceAccount<'info, anchor_spl::token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", bet.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    
+    pub token_program: Option<Interface<'info, anchor_spl::token_interface::TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(winning_option: u8, actual_price: u64)]
+pub struct ResolveBet<'info> {
+    #[account(
+        mut,
+        constraint = !bet.resolved @ FluxError::BetAlreadyResolved,
+        constraint = !bet.cancelled @ FluxError::BetCancelled,
+        constraint = bet.oracle.is_none() @ FluxError::OracleResolvedBet,
+        constraint = bet.challenge_deadline.is_none() @ FluxError::ResolutionAlreadyProposed,
+        constraint = Clock::get().unwrap().unix_timestamp >= bet.end_time @ FluxError::BetNotEnded,
+        constraint = winning_option < bet.options.len() as u8 @ FluxError::InvalidOptionIndex
+    )]
+    pub bet: Account<'info, Bet>,
+    
+    #[account(mut)]
+    pub resolver: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = group.key() == bet.group
+    )]
+    pub group: Account<'info, Group>,
+    
+    #[account(
+        seeds = [b"group_member", group.key().as_ref(), resolver.key().as_ref()],
+        bump = group_member.bump,
+        constraint = group_member.role.can_resolve(bet.creator == resolver.key()) @ FluxError::UnauthorizedResolver
+    )]
+    pub group_member: Account<'info, GroupMember>,
+    
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    #[account(
+        mut,
+        constraint = resolver_token_account.owner == resolver.key() @ FluxError::InvalidTokenAccountOwner,
+        constraint = resolver_token_account.mint == bet.mint @ FluxError::InvalidMint
+    )]
+    pub resolver_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", bet.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(address = bet.mint @ FluxError::InvalidMint)]
+    pub mint: Option<InterfaceAccount<'info, anchor_spl::token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", bet.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    
+    pub token_program: Option<Interface<'info, anchor_spl::token_interface::TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DisputeResolution<'info> {
+    #[account(
+        mut,
+        constraint = !bet.resolved @ FluxError::BetAlreadyResolved,
+        constraint = bet.challenge_deadline.is_some() @ FluxError::ResolutionNotProposed,
+        constraint = bet.disputer.is_none() @ FluxError::AlreadyDisputed
+    )]
+    pub bet: Account<'info, Bet>,
+    
+    #[account(
+        constraint = group.key() == bet.group
+    )]
+    pub group: Account<'info, Group>,
+    
+    #[account(mut)]
+    pub disputer: Signer<'info>,
+    
+    #[account(
+        seeds = [b"group_member", group.key().as_ref(), disputer.key().as_ref()],
+        bump = group_member.bump,
+        constraint = group_member.user == disputer.key() @ FluxError::NotGroupMember
+    )]
+    pub group_member: Account<'info, GroupMember>,
+    
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    #[account(
+        mut,
+        constraint = disputer_token_account.owner == disputer.key() @ FluxError::InvalidTokenAccountOwner,
+        constraint = disputer_token_account.mint == bet.mint @ FluxError::InvalidMint
+    )]
+    pub disputer_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", bet.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(address = bet.mint @ FluxError::InvalidMint)]
+    pub mint: Option<InterfaceAccount<'info, anchor_spl::token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", bet.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    
+    pub token_program: Option<Interface<'info, anchor_spl::token_interface::TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(winning_option: u8, actual_price: u64)]
+pub struct SettleDispute<'info> {
+    #[account(
+        mut,
+        constraint = !bet.resolved @ FluxError::BetAlreadyResolved,
+        constraint = bet.disputer.is_some() @ FluxError::NotDisputed,
+        constraint = winning_option < bet.options.len() as u8 @ FluxError::InvalidOptionIndex
+    )]
+    pub bet: Account<'info, Bet>,
+    
+    #[account(
+        constraint = arbiter.key() == platform.admin || arbiter.key() == platform.arbiter @ FluxError::UnauthorizedArbiter
+    )]
+    pub arbiter: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = group.key() == bet.group
+    )]
+    pub group: Account<'info, Group>,
+    
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    /// CHECK: Receives SOL bonds, checked against the bet's proposer
+    #[account(
+        mut,
+        constraint = Some(proposer.key()) == bet.proposer @ FluxError::InvalidRecipient
+    )]
+    pub proposer: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = Some(proposer_token_account.owner) == bet.proposer @ FluxError::InvalidTokenAccountOwner,
+        constraint = proposer_token_account.mint == bet.mint @ FluxError::InvalidMint
+    )]
+    pub proposer_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    /// CHECK: Receives SOL bonds, checked against the bet's disputer
+    #[account(
+        mut,
+        constraint = Some(disputer.key()) == bet.disputer @ FluxError::InvalidRecipient
+    )]
+    pub disputer: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = Some(disputer_token_account.owner) == bet.disputer @ FluxError::InvalidTokenAccountOwner,
+        constraint = disputer_token_account.mint == bet.mint @ FluxError::InvalidMint
+    )]
+    pub disputer_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", bet.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(address = bet.mint @ FluxError::InvalidMint)]
+    pub mint: Option<InterfaceAccount<'info, anchor_spl::token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", bet.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    
+    pub token_program: Option<Interface<'info, anchor_spl::token_interface::TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct FinalizeResolution<'info> {
+    #[account(
+        mut,
+        constraint = !bet.resolved @ FluxError::BetAlreadyResolved,
+        constraint = bet.challenge_deadline.is_some() @ FluxError::ResolutionNotProposed,
+        constraint = bet.disputer.is_none() @ FluxError::AlreadyDisputed
+    )]
+    pub bet: Account<'info, Bet>,
+    
+    #[account(
+        mut,
+        constraint = group.key() == bet.group
+    )]
+    pub group: Account<'info, Group>,
+    
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    /// CHECK: Receives SOL bonds, checked against the bet's proposer
+    #[account(
+        mut,
+        constraint = Some(proposer.key()) == bet.proposer @ FluxError::InvalidRecipient
+    )]
+    pub proposer: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = Some(proposer_token_account.owner) == bet.proposer @ FluxError::InvalidTokenAccountOwner,
+        constraint = proposer_token_account.mint == bet.mint @ FluxError::InvalidMint
+    )]
+    pub proposer_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", bet.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(address = bet.mint @ FluxError::InvalidMint)]
+    pub mint: Option<InterfaceAccount<'info, anchor_spl::token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", bet.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    
+    pub token_program: Option<Interface<'info, anchor_spl::token_interface::TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveFromOracle<'info> {
+    #[account(
+        mut,
+        constraint = !bet.resolved @ FluxError::BetAlreadyResolved,
+        constraint = !bet.cancelled @ FluxError::BetCancelled,
+        constraint = bet.oracle.is_some() @ FluxError::OracleNotConfigured
+    )]
+    pub bet: Account<'info, Bet>,
+    
+    /// CHECK: Owner, discriminator and feed id are validated when the price is read
+    #[account(
+        constraint = bet.oracle.as_ref().is_some_and(|o| o.price_feed == price_feed.key()) @ FluxError::InvalidOracleAccount
+    )]
+    pub price_feed: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        constraint = group.key() == bet.group
+    )]
+    pub group: Account<'info, Group>,
+    
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWinnings<'info> {
+    #[account(
+        mut,
+        constraint = bet.resolved @ FluxError::BetNotResolved,
+        seeds = [b"bet", bet.group.as_ref(), bet.id.as_bytes()],
+        bump = bet.bump
+    )]
+    pub bet: Account<'info, Bet>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_bet", bet.key().as_ref(), user.key().as_ref(), &[user_bet.option_index]],
+        bump = user_bet.bump,
+        constraint = user_bet.user == user.key(),
+        constraint = !user_bet.claimed @ FluxError::NoWinningsToClaim,
+        constraint = Some(user_bet.option_index) == bet.winning_option @ FluxError::NoWinningsToClaim
+    )]
+    pub user_bet: Account<'info, UserBet>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"group_member", bet.group.as_ref(), user.key().as_ref()],
+        bump = group_member.bump
+    )]
+    pub group_member: Account<'info, GroupMember>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump,
+        constraint = user_profile.user == user.key()
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"bet_record", bet.key().as_ref(), user.key().as_ref()],
+        bump = bet_record.bump
+    )]
+    pub bet_record: Account<'info, BetRecord>,
+    
+    #[account(
+        mut,
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + 32 + 8 + 8 + 8 + 1,
+        seeds = [b"fee_ledger", bet.mint.as_ref()],
+        bump
+    )]
+    pub fee_ledger: Account<'info, FeeLedger>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", bet.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ FluxError::InvalidTokenAccountOwner,
+        constraint = user_token_account.mint == bet.mint @ FluxError::InvalidMint
+    )]
+    pub user_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(address = bet.mint @ FluxError::InvalidMint)]
+    pub mint: Option<InterfaceAccount<'info, anchor_spl::token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", bet.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    
+    pub token_program: Option<Interface<'info, anchor_spl::token_interface::TokenInterface>>,
+    pub system_program: Program<'info, System>,
+} 
+
+#[derive(Accounts)]
+pub struct CashOut<'info> {
+    #[account(
+        mut,
+        constraint = !bet.resolved @ FluxError::BetAlreadyResolved,
+        constraint = !bet.cancelled @ FluxError::BetCancelled,
+        constraint = Clock::get().unwrap().unix_timestamp < bet.end_time @ FluxError::BetPeriodEnded,
+        constraint = bet.challenge_deadline.is_none() @ FluxError::ResolutionAlreadyProposed,
+        constraint = bet.cash_out.is_some() @ FluxError::CashOutDisabled,
+        seeds = [b"bet", bet.group.as_ref(), bet.id.as_bytes()],
+        bump = bet.bump
+    )]
+    pub bet: Account<'info, Bet>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_bet", bet.key().as_ref(), user.key().as_ref(), &[user_bet.option_index]],
+        bump = user_bet.bump,
+        constraint = user_bet.user == user.key()
+    )]
+    pub user_bet: Account<'info, UserBet>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"group_member", bet.group.as_ref(), user.key().as_ref()],
+        bump = group_member.bump
+    )]
+    pub group_member: Account<'info, GroupMember>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump,
+        constraint = user_profile.user == user.key()
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"bet_record", bet.key().as_ref(), user.key().as_ref()],
+        bump = bet_record.bump
+    )]
+    pub bet_record: Account<'info, BetRecord>,
+    
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + 32 + 8 + 8 + 8 + 1,
+        seeds = [b"fee_ledger", bet.mint.as_ref()],
+        bump
+    )]
+    pub fee_ledger: Account<'info, FeeLedger>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", bet.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ FluxError::InvalidTokenAccountOwner,
+        constraint = user_token_account.mint == bet.mint @ FluxError::InvalidMint
+    )]
+    pub user_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(address = bet.mint @ FluxError::InvalidMint)]
+    pub mint: Option<InterfaceAccount<'info, anchor_spl::token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", bet.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    
+    pub token_program: Option<Interface<'info, anchor_spl::token_interface::TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClosePosition<'info> {
+    #[account(
+        constraint = bet.resolved @ FluxError::BetNotResolved,
+        // stakes on a parimutuel bet nobody won are refunded rather than lost
+        constraint = !refunds_stakes(&bet) @ FluxError::PositionNotLost,
+        seeds = [b"bet", bet.group.as_ref(), bet.id.as_bytes()],
+        bump = bet.bump
+    )]
+    pub bet: Account<'info, Bet>,
+    
+    #[account(
+        mut,
+        close = user,
+        seeds = [b"user_bet", bet.key().as_ref(), user.key().as_ref(), &[user_bet.option_index]],
+        bump = user_bet.bump,
+        constraint = user_bet.user == user.key(),
+        constraint = Some(user_bet.option_index) != bet.winning_option @ FluxError::PositionNotLost
+    )]
+    pub user_bet: Account<'info, UserBet>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"group_member", bet.group.as_ref(), user.key().as_ref()],
+        bump = group_member.bump
+    )]
+    pub group_member: Account<'info, GroupMember>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump,
+        constraint = user_profile.user == user.key()
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"bet_record", bet.key().as_ref(), user.key().as_ref()],
+        bump = bet_record.bump
+    )]
+    pub bet_record: Account<'info, BetRecord>,
+}
+
+#[derive(Accounts)]
+pub struct CancelBet<'info> {
+    #[account(
+        mut,
+        constraint = !bet.resolved @ FluxError::BetAlreadyResolved,
+        constraint = !bet.cancelled @ FluxError::BetCancelled,
+        constraint = bet.challenge_deadline.is_none() @ FluxError::ResolutionAlreadyProposed,
+        // the creator can only pull the bet while it's open, after that only the platform admin can
+        constraint = (bet.creator == authority.key() && Clock::get().unwrap().unix_timestamp < bet.end_time)
+            || platform.admin == authority.key() @ FluxError::UnauthorizedCanceller
+    )]
+    pub bet: Account<'info, Bet>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = group.key() == bet.group
+    )]
+    pub group: Account<'info, Group>,
+    
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawBankroll<'info> {
+    #[account(
+        mut,
+        constraint = bet.resolved || bet.cancelled @ FluxError::BetNotResolved,
+        constraint = !bet.bankroll_withdrawn @ FluxError::BankrollAlreadyWithdrawn,
+        constraint = bet.house == Some(house.key()) @ FluxError::NotBetHouse,
+        seeds = [b"bet", bet.group.as_ref(), bet.id.as_bytes()],
+        bump = bet.bump
+    )]
+    pub bet: Account<'info, Bet>,
     
     #[account(mut)]
-    pub treasury_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    pub house: Signer<'info>,
+    
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", bet.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = house_token_account.owner == house.key() @ FluxError::InvalidTokenAccountOwner,
+        constraint = house_token_account.mint == bet.mint @ FluxError::InvalidMint
+    )]
+    pub house_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(address = bet.mint @ FluxError::InvalidMint)]
+    pub mint: Option<InterfaceAccount<'info, anchor_spl::token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", bet.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
+    
+    pub token_program: Option<Interface<'info, anchor_spl::token_interface::TokenInterface>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRefund<'info> {
+    #[account(
+        mut,
+        constraint = refunds_stakes(&bet) @ FluxError::BetNotCancelled,
+        seeds = [b"bet", bet.group.as_ref(), bet.id.as_bytes()],
+        bump = bet.bump
+    )]
+    pub bet: Account<'info, Bet>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_bet", bet.key().as_ref(), user.key().as_ref(), &[user_bet.option_index]],
+        bump = user_bet.bump,
+        constraint = user_bet.user == user.key(),
+        constraint = !user_bet.claimed @ FluxError::RefundAlreadyClaimed
+    )]
+    pub user_bet: Account<'info, UserBet>,
     
     #[account(mut)]
-    pub user_token_account: Account<'info, anchor_spl::token::TokenAccount>,
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"group_member", bet.group.as_ref(), user.key().as_ref()],
+        bump = group_member.bump
+    )]
+    pub group_member: Account<'info, GroupMember>,
+    
+    #[account(
+        mut,
+        seeds = [b"user_profile", user.key().as_ref()],
+        bump = user_profile.bump,
+        constraint = user_profile.user == user.key()
+    )]
+    pub user_profile: Account<'info, UserProfile>,
+    
+    #[account(
+        mut,
+        seeds = [b"bet_record", bet.key().as_ref(), user.key().as_ref()],
+        bump = bet_record.bump
+    )]
+    pub bet_record: Account<'info, BetRecord>,
+    
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", bet.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = user_token_account.owner == user.key() @ FluxError::InvalidTokenAccountOwner,
+        constraint = user_token_account.mint == bet.mint @ FluxError::InvalidMint
+    )]
+    pub user_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(address = bet.mint @ FluxError::InvalidMint)]
+    pub mint: Option<InterfaceAccount<'info, anchor_spl::token_interface::Mint>>,
+    
+    #[account(
+        mut,
+        seeds = [b"escrow", bet.key().as_ref()],
+        bump
+    )]
+    pub escrow: Option<SystemAccount<'info>>,
     
-    pub token_program: Program<'info, anchor_spl::token::Token>,
+    pub token_program: Option<Interface<'info, anchor_spl::token_interface::TokenInterface>>,
     pub system_program: Program<'info, System>,
-} 
\ No newline at end of file
+}
+
+#[derive(Accounts)]
+pub struct WithdrawFees<'info> {
+    #[account(
+        seeds = [b"platform"],
+        bump = platform.bump,
+        constraint = platform.admin == admin.key() @ FluxError::UnauthorizedPlatformAdmin
+    )]
+    pub platform: Account<'info, Platform>,
+    
+    pub admin: Signer<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"fee_ledger", fee_ledger.mint.as_ref()],
+        bump = fee_ledger.bump
+    )]
+    pub fee_ledger: Account<'info, FeeLedger>,
+    
+    #[account(
+        mut,
+        seeds = [b"vault", fee_ledger.mint.as_ref()],
+        bump
+    )]
+    pub treasury_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    #[account(
+        mut,
+        constraint = destination_token_account.owner == platform.treasury @ FluxError::InvalidTokenAccountOwner,
+        constraint = destination_token_account.mint == fee_ledger.mint @ FluxError::InvalidMint
+    )]
+    pub destination_token_account: Option<InterfaceAccount<'info, anchor_spl::token_interface::TokenAccount>>,
+    
+    /// CHECK: Receives SOL fees, checked against the platform treasury
+    #[account(
+        mut,
+        address = platform.treasury @ FluxError::InvalidRecipient
+    )]
+    pub treasury: Option<UncheckedAccount<'info>>,
+    
+    #[account(address = fee_ledger.mint @ FluxError::InvalidMint)]
+    pub mint: Option<InterfaceAccount<'info, anchor_spl::token_interface::Mint>>,
+    
+    pub token_program: Option<Interface<'info, anchor_spl::token_interface::TokenInterface>>,
+}
@@ -1,45 +1,301 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+use anchor_spl::token_2022::spl_token_2022;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 use crate::errors::FluxError;
+use crate::state::{
+    Bet, BetRecord, CashOut, CashOutCurve, ClaimRefund, ClaimWinnings, DisputeResolution, FeeLedger,
+    FinalizeResolution, FundBet, PayoutMode, PlaceBet, Platform, PriceRange, ResolveBet, SettleDispute,
+    SettlementAsset, UserProfile, WithdrawBankroll,
+};
 
-// calculate winnings
+// calculate platform fee
+pub fn calculate_fee(amount: u64, fee_percentage: u16) -> Result<u64> {
+    let fee_amount = (amount as u128)
+        .checked_mul(fee_percentage as u128)
+        .ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_div(10000)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    
+    u64::try_from(fee_amount).map_err(|_| ProgramError::ArithmeticOverflow.into())
+}
+
+// fee percentage at most 10000 (100%)
+pub fn validate_fee_percentage(fee_percentage: u16) -> Result<()> {
+    require!(fee_percentage <= 10000, FluxError::InvalidFeePercentage);
+    Ok(())
+}
+
+// record a fee retained in the treasury, setting up the ledger on first use
+pub fn accrue_fees(
+    fee_ledger: &mut FeeLedger,
+    mint: Pubkey,
+    bump: u8,
+    fee_amount: u64,
+) -> Result<()> {
+    if fee_ledger.mint == Pubkey::default() {
+        fee_ledger.mint = mint;
+        fee_ledger.bump = bump;
+    }
+    
+    fee_ledger.accrued_fees = fee_ledger
+        .accrued_fees
+        .checked_add(fee_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    fee_ledger.total_collected = fee_ledger
+        .total_collected
+        .checked_add(fee_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    
+    Ok(())
+}
+
+// calculate winnings, returns the payout and the platform fee retained from it
 pub fn calculate_winnings(
     bet_amount: u64,
     odds: u16,
-    total_pool: u64,
+    available: u64,
     fee_percentage: u16,
-) -> Result<u64> {
-    let raw_winnings = bet_amount
+) -> Result<(u64, u64)> {
+    let raw_winnings = fixed_odds_payout(bet_amount, odds)?;
+    
+    // apply platform fee
+    let fee_amount = calculate_fee(raw_winnings, fee_percentage)?;
+    
+    let final_winnings = raw_winnings
+        .checked_sub(fee_amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    
+    // Ensure we don't pay out more than the pool and bankroll hold
+    if final_winnings > available {
+        return Err(FluxError::InsufficientFunds.into());
+    }
+    
+    Ok((final_winnings, fee_amount))
+}
+
+// gross fixed odds payout of a stake, before the platform fee
+pub fn fixed_odds_payout(stake: u64, odds: u16) -> Result<u64> {
+    let payout = stake
         .checked_mul(odds as u64)
         .ok_or(ProgramError::ArithmeticOverflow)?
         .checked_div(100)
         .ok_or(ProgramError::ArithmeticOverflow)?;
     
-    // apply platform fee
-    let fee_amount = raw_winnings
-        .checked_mul(fee_percentage as u64)
+    Ok(payout)
+}
+
+// liability added by a stake, rounded up so the sum never understates a claim's payout
+pub fn fixed_odds_liability(stake: u64, odds: u16) -> Result<u64> {
+    let liability = stake
+        .checked_mul(odds as u64)
         .ok_or(ProgramError::ArithmeticOverflow)?
-        .checked_div(10000)
+        .div_ceil(100);
+    
+    Ok(liability)
+}
+
+// payout net of the bet's fee a wager just taken would get were its option to win as things stand
+pub fn expected_payout(
+    bet: &Bet,
+    option_index: u8,
+    received: u64,
+    odds: u16,
+    shares: u64,
+) -> Result<u64> {
+    let gross = match bet.payout_mode {
+        PayoutMode::FixedOdds | PayoutMode::DynamicOdds => fixed_odds_payout(received, odds)?,
+        PayoutMode::Lmsr => shares,
+        PayoutMode::Parimutuel => {
+            let payout = (received as u128)
+                .checked_mul(bet.total_pool as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?
+                .checked_div(bet.bets_per_option[option_index as usize] as u128)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            
+            u64::try_from(payout).map_err(|_| ProgramError::ArithmeticOverflow)?
+        }
+    };
+    
+    let fee_amount = calculate_fee(gross, bet.fee_percentage)?;
+    
+    Ok(gross.checked_sub(fee_amount).unwrap())
+}
+
+// fixed odds, dynamic odds and LMSR bets must hold enough to pay whichever option wins
+pub fn check_solvency(bet: &Bet) -> Result<()> {
+    if bet.payout_mode == PayoutMode::Parimutuel {
+        return Ok(());
+    }
+    
+    let available = (bet.total_pool as u128) + (bet.bankroll as u128);
+    require!(
+        bet.liabilities.iter().all(|&liability| liability as u128 <= available),
+        FluxError::LiabilityExceeded
+    );
+    
+    Ok(())
+}
+
+// reprice dynamic odds from each option's share of the pool, shaded by the overround,
+// options nobody has backed yet keep their current odds
+pub fn dynamic_odds(bet: &Bet) -> Result<Vec<u16>> {
+    let margin = 10000 + bet.overround_bps as u128;
+    
+    bet.bets_per_option
+        .iter()
+        .zip(bet.odds.iter())
+        .map(|(&option_pool, &current)| {
+            if option_pool == 0 {
+                return Ok(current);
+            }
+            
+            let odds = (bet.total_pool as u128)
+                .checked_mul(100 * 10000)
+                .ok_or(ProgramError::ArithmeticOverflow)?
+                .checked_div((option_pool as u128) * margin)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+            
+            // never below evens, and capped at what odds can hold
+            Ok(odds.clamp(100, u16::MAX as u128) as u16)
+        })
+        .collect()
+}
+
+// what the house takes back after settlement, stakes and bankroll less the winners' payouts
+pub fn bankroll_remainder(bet: &Bet) -> Result<u64> {
+    // stakes go back to the bettors on a cancelled bet
+    if bet.cancelled {
+        return Ok(bet.bankroll);
+    }
+    
+    let winning_option = bet.winning_option.ok_or(FluxError::BetNotResolved)? as usize;
+    let held = bet
+        .total_pool
+        .checked_add(bet.bankroll)
         .ok_or(ProgramError::ArithmeticOverflow)?;
     
+    Ok(held.saturating_sub(bet.liabilities[winning_option]))
+}
+
+// move the bet to the user's past bets once they hold no open position on it
+pub fn settle_bet_record(bet_record: &mut BetRecord, user_profile: &mut UserProfile) {
+    if bet_record.open_positions == 0 && !bet_record.settled {
+        bet_record.settled = true;
+        user_profile.active_bet_count = user_profile.active_bet_count.checked_sub(1).unwrap();
+        user_profile.past_bet_count = user_profile.past_bet_count.checked_add(1).unwrap();
+    }
+}
+
+// stakes go back on a cancelled bet, and on a parimutuel bet nobody backed the winning option of
+pub fn refunds_stakes(bet: &Bet) -> bool {
+    if bet.cancelled {
+        return true;
+    }
+    
+    bet.resolved
+        && bet.payout_mode == PayoutMode::Parimutuel
+        && bet
+            .winning_option
+            .is_some_and(|option| bet.bets_per_option[option as usize] == 0)
+}
+
+// calculate parimutuel winnings, winners split the pool pro rata by stake
+pub fn calculate_parimutuel_winnings(
+    bet_amount: u64,
+    winning_pool: u64,
+    total_pool: u64,
+    fee_percentage: u16,
+) -> Result<(u64, u64)> {
+    if winning_pool == 0 {
+        return Err(FluxError::NoWinningsToClaim.into());
+    }
+    
+    // u128 so stake * pool can't overflow, rounding down keeps the sum of payouts within the pool
+    let raw_winnings = (bet_amount as u128)
+        .checked_mul(total_pool as u128)
+        .ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_div(winning_pool as u128)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    let raw_winnings = u64::try_from(raw_winnings).map_err(|_| ProgramError::ArithmeticOverflow)?;
+    
+    // apply platform fee
+    let fee_amount = calculate_fee(raw_winnings, fee_percentage)?;
+    
     let final_winnings = raw_winnings
         .checked_sub(fee_amount)
         .ok_or(ProgramError::ArithmeticOverflow)?;
     
-    // Ensure we don't pay out more than the total pool
-    if final_winnings > total_pool {
-        return Err(FluxError::InsufficientFunds.into());
+    Ok((final_winnings, fee_amount))
+}
+
+// calculate the gross value of selling part of a position before the bet closes
+pub fn calculate_cash_out_value(
+    bet: &Bet,
+    option_index: u8,
+    stake: u64,
+    current_time: i64,
+) -> Result<u64> {
+    let curve = bet.cash_out.ok_or(FluxError::CashOutDisabled)?;
+    let option_pool = bet.bets_per_option[option_index as usize];
+    
+    // fixed odds positions are worth their payout weighted by the option's pool share,
+    // a parimutuel position's expected payout is its stake
+    let fair_value = match bet.payout_mode {
+        PayoutMode::FixedOdds => (stake as u128)
+            .checked_mul(bet.odds[option_index as usize] as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_mul(option_pool as u128)
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .checked_div((bet.total_pool as u128) * 100)
+            .ok_or(ProgramError::ArithmeticOverflow)?
+            .min(stake as u128),
+        PayoutMode::Parimutuel => stake as u128,
+        PayoutMode::Lmsr | PayoutMode::DynamicOdds => return err!(FluxError::CashOutNotSupported),
+    };
+    
+    // the return decays linearly from max to min as the bet approaches end_time
+    let duration = bet.end_time.saturating_sub(bet.created_at).max(1);
+    let time_remaining = bet.end_time.saturating_sub(current_time).clamp(0, duration);
+    let spread = curve.max_return_bps.saturating_sub(curve.min_return_bps) as u128;
+    let return_bps = (curve.min_return_bps as u128)
+        + spread * (time_remaining as u128) / (duration as u128);
+    
+    let value = fair_value
+        .checked_mul(return_bps)
+        .ok_or(ProgramError::ArithmeticOverflow)?
+        .checked_div(10000)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    
+    u64::try_from(value).map_err(|_| ProgramError::ArithmeticOverflow.into())
+}
+
+// validate cash-out curve
+pub fn validate_cash_out_curve(curve: &CashOutCurve) -> Result<()> {
+    if curve.max_return_bps > 10000 || curve.min_return_bps > curve.max_return_bps {
+        return Err(FluxError::InvalidCashOutCurve.into());
     }
     
-    Ok(final_winnings)
+    Ok(())
 }
 
 // validate options and odds
 pub fn validate_options_and_odds(
-    options: &Vec<String>,
-    odds: &Vec<u16>
+    options: &[String],
+    odds: Option<&[u16]>,
+    payout_mode: PayoutMode,
 ) -> Result<()> {
-    if options.len() != odds.len() {
-        return Err(FluxError::OptionOddsMismatch.into());
+    match odds {
+        Some(odds) if options.len() != odds.len() => {
+            return Err(FluxError::OptionOddsMismatch.into());
+        }
+        // dynamic odds open at the creator's odds
+        None if matches!(payout_mode, PayoutMode::FixedOdds | PayoutMode::DynamicOdds) => {
+            return Err(FluxError::OddsRequired.into());
+        }
+        _ => {}
     }
     
     if options.len() < 2 {
@@ -55,15 +311,17 @@ pub fn validate_options_and_odds(
 
 // transfer tokens
 pub fn transfer_tokens_from_treasury<'a>(
-    token_program: &Program<'a, anchor_spl::token::Token>,
-    from: &Account<'a, anchor_spl::token::TokenAccount>,
-    to: &Account<'a, anchor_spl::token::TokenAccount>,
+    token_program: &Interface<'a, TokenInterface>,
+    from: &InterfaceAccount<'a, TokenAccount>,
+    mint: &InterfaceAccount<'a, Mint>,
+    to: &InterfaceAccount<'a, TokenAccount>,
     authority: &AccountInfo<'a>,
     seeds: &[&[&[u8]]],
     amount: u64,
 ) -> Result<()> {
-    let transfer_instruction = anchor_spl::token::Transfer {
+    let transfer_instruction = TransferChecked {
         from: from.to_account_info(),
+        mint: mint.to_account_info(),
         to: to.to_account_info(),
         authority: authority.to_account_info(),
     };
@@ -74,5 +332,283 @@ pub fn transfer_tokens_from_treasury<'a>(
         seeds,
     );
     
-    anchor_spl::token::transfer(ctx, amount)
-} 
\ No newline at end of file
+    token_interface::transfer_checked(ctx, amount, mint.decimals)
+}
+
+// transfer fee config of a Token-2022 mint, None for mints without the extension
+fn transfer_fee_config(mint: &AccountInfo) -> Result<Option<TransferFeeConfig>> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(None);
+    }
+    
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    
+    Ok(mint_state.get_extension::<TransferFeeConfig>().ok().copied())
+}
+
+// a transfer fee is the only Token-2022 extension the vault accounting handles, others can
+// move or freeze vault funds, hook transfers, or change what an amount means
+pub fn validate_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+    
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+    require!(
+        mint_state
+            .get_extension_types()?
+            .iter()
+            .all(|extension| *extension == ExtensionType::TransferFeeConfig),
+        FluxError::UnsupportedMintExtension
+    );
+    
+    Ok(())
+}
+
+// fee withheld by the mint when `amount` is transferred
+pub fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let Some(config) = transfer_fee_config(mint)? else {
+        return Ok(0);
+    };
+    
+    config
+        .calculate_epoch_fee(Clock::get()?.epoch, amount)
+        .ok_or(ProgramError::ArithmeticOverflow.into())
+}
+
+// amount to send so the recipient is credited `net` after the mint's transfer fee
+pub fn amount_with_transfer_fee(mint: &AccountInfo, net: u64) -> Result<u64> {
+    let Some(config) = transfer_fee_config(mint)? else {
+        return Ok(net);
+    };
+    
+    let fee = config
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, net)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    
+    net.checked_add(fee).ok_or(ProgramError::ArithmeticOverflow.into())
+}
+
+// accounts holding a bet's funds, the vault for its mint or its lamport escrow
+pub struct BetFunds<'info> {
+    asset: SettlementAsset,
+    bet: Pubkey,
+    platform: Account<'info, Platform>,
+    vault: Option<InterfaceAccount<'info, TokenAccount>>,
+    mint: Option<InterfaceAccount<'info, Mint>>,
+    token_program: Option<Interface<'info, TokenInterface>>,
+    escrow: Option<SystemAccount<'info>>,
+    escrow_bump: Option<u8>,
+    system_program: Program<'info, System>,
+}
+
+// collect the accounts a bet settles through from any context that moves its funds
+macro_rules! impl_bet_funds {
+    ($($accounts:ident),+ $(,)?) => {
+        $(
+            impl<'info> $accounts<'info> {
+                pub fn bet_funds(&self, escrow_bump: Option<u8>) -> BetFunds<'info> {
+                    BetFunds {
+                        asset: self.bet.settlement_asset,
+                        bet: self.bet.key(),
+                        platform: self.platform.clone(),
+                        vault: self.treasury_token_account.clone(),
+                        mint: self.mint.clone(),
+                        token_program: self.token_program.clone(),
+                        escrow: self.escrow.clone(),
+                        escrow_bump,
+                        system_program: self.system_program.clone(),
+                    }
+                }
+            }
+        )+
+    };
+}
+
+impl_bet_funds!(
+    FundBet,
+    PlaceBet,
+    CashOut,
+    ResolveBet,
+    DisputeResolution,
+    SettleDispute,
+    FinalizeResolution,
+    ClaimWinnings,
+    WithdrawBankroll,
+    ClaimRefund,
+);
+
+impl<'info> BetFunds<'info> {
+    // move `amount` from the payer into the bet, returns what the bet is credited
+    pub fn deposit(
+        &self,
+        payer: &Signer<'info>,
+        payer_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+        amount: u64,
+    ) -> Result<u64> {
+        match self.asset {
+            SettlementAsset::Token => {
+                let (vault, mint, token_program) = self.token_accounts()?;
+                let from = payer_token_account.ok_or(FluxError::SettlementAccountsMissing)?;
+                
+                token_interface::transfer_checked(
+                    CpiContext::new(
+                        token_program.to_account_info(),
+                        TransferChecked {
+                            from: from.to_account_info(),
+                            mint: mint.to_account_info(),
+                            to: vault.to_account_info(),
+                            authority: payer.to_account_info(),
+                        },
+                    ),
+                    amount,
+                    mint.decimals,
+                )?;
+                
+                // only what reaches the vault is credited when the mint withholds a transfer fee
+                let fee = transfer_fee(&mint.to_account_info(), amount)?;
+                Ok(amount.checked_sub(fee).unwrap())
+            }
+            SettlementAsset::NativeSol => {
+                let escrow = self.escrow.as_ref().ok_or(FluxError::SettlementAccountsMissing)?;
+                
+                system_program::transfer(
+                    CpiContext::new(
+                        self.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: payer.to_account_info(),
+                            to: escrow.to_account_info(),
+                        },
+                    ),
+                    amount,
+                )?;
+                
+                Ok(amount)
+            }
+        }
+    }
+    
+    // move `amount` into the bet, grossed up so the bet holds all of it after any transfer fee
+    pub fn deposit_exact(
+        &self,
+        payer: &Signer<'info>,
+        payer_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+        amount: u64,
+    ) -> Result<()> {
+        let gross_amount = match (self.asset, &self.mint) {
+            (SettlementAsset::Token, Some(mint)) => {
+                amount_with_transfer_fee(&mint.to_account_info(), amount)?
+            }
+            _ => amount,
+        };
+        
+        self.deposit(payer, payer_token_account, gross_amount)?;
+        
+        Ok(())
+    }
+    
+    // pay `amount` out of the bet, to the recipient's token account for token bets
+    pub fn withdraw(
+        &self,
+        recipient: &AccountInfo<'info>,
+        recipient_token_account: Option<&InterfaceAccount<'info, TokenAccount>>,
+        amount: u64,
+    ) -> Result<()> {
+        match self.asset {
+            SettlementAsset::Token => {
+                let (vault, mint, token_program) = self.token_accounts()?;
+                let to = recipient_token_account.ok_or(FluxError::SettlementAccountsMissing)?;
+                
+                let platform_seeds = &[b"platform".as_ref(), &[self.platform.bump]];
+                let seeds = &[&platform_seeds[..]];
+                
+                transfer_tokens_from_treasury(
+                    token_program,
+                    vault,
+                    mint,
+                    to,
+                    &self.platform.to_account_info(),
+                    seeds,
+                    amount,
+                )
+            }
+            SettlementAsset::NativeSol => {
+                let escrow = self.escrow.as_ref().ok_or(FluxError::SettlementAccountsMissing)?;
+                let escrow_bump = self.escrow_bump.ok_or(FluxError::SettlementAccountsMissing)?;
+                
+                let escrow_seeds = &[b"escrow".as_ref(), self.bet.as_ref(), &[escrow_bump]];
+                let seeds = &[&escrow_seeds[..]];
+                
+                system_program::transfer(
+                    CpiContext::new_with_signer(
+                        self.system_program.to_account_info(),
+                        system_program::Transfer {
+                            from: escrow.to_account_info(),
+                            to: recipient.clone(),
+                        },
+                        seeds,
+                    ),
+                    amount,
+                )
+            }
+        }
+    }
+    
+    // native SOL bets keep their fees in the fee ledger account, token fees stay in the vault
+    pub fn retain_fee(&self, fee_ledger: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        if self.asset == SettlementAsset::NativeSol && amount > 0 {
+            self.withdraw(fee_ledger, None, amount)?;
+        }
+        
+        Ok(())
+    }
+    
+    fn token_accounts(
+        &self,
+    ) -> Result<(
+        &InterfaceAccount<'info, TokenAccount>,
+        &InterfaceAccount<'info, Mint>,
+        &Interface<'info, TokenInterface>,
+    )> {
+        match (&self.vault, &self.mint, &self.token_program) {
+            (Some(vault), Some(mint), Some(token_program)) => Ok((vault, mint, token_program)),
+            _ => err!(FluxError::SettlementAccountsMissing),
+        }
+    }
+}
+
+// validate price ranges, one [lower, upper) band per option in ascending order
+pub fn validate_price_ranges(
+    options: &[String],
+    price_ranges: &[PriceRange],
+) -> Result<()> {
+    if price_ranges.len() != options.len() {
+        return Err(FluxError::PriceRangeCountMismatch.into());
+    }
+    
+    if price_ranges.iter().any(|range| range.lower >= range.upper) {
+        return Err(FluxError::InvalidPriceRange.into());
+    }
+    
+    for pair in price_ranges.windows(2) {
+        if pair[1].lower > pair[0].upper {
+            return Err(FluxError::PriceRangeGap.into());
+        }
+        if pair[1].lower < pair[0].upper {
+            return Err(FluxError::PriceRangeOverlap.into());
+        }
+    }
+    
+    Ok(())
+}
+
+// find the option whose price range contains the price
+pub fn winning_option_for_price(price_ranges: &[PriceRange], price: u64) -> Result<u8> {
+    price_ranges
+        .iter()
+        .position(|range| range.lower <= price && price < range.upper)
+        .map(|index| index as u8)
+        .ok_or(FluxError::PriceOutsideRanges.into())
+}
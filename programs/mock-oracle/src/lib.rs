@@ -0,0 +1,78 @@
+use anchor_lang::prelude::*;
+
+declare_id!("4HmqiucgB5SaGVf3Mm5CXUFBLMiXH99MJhoSMehZ2dt2");
+
+// Writes price accounts with the same layout as the Pyth receiver's PriceUpdateV2,
+// so flux-betting can be tested against locally controlled prices.
+#[program]
+pub mod mock_oracle {
+    use super::*;
+    
+    pub fn set_price(
+        ctx: Context<SetPrice>,
+        feed_id: [u8; 32],
+        price: i64,
+        conf: u64,
+        exponent: i32,
+        publish_time: i64,
+    ) -> Result<()> {
+        let price_update = &mut ctx.accounts.price_update;
+        
+        price_update.write_authority = ctx.accounts.payer.key();
+        price_update.verification_level = VerificationLevel::Full;
+        price_update.price_message = PriceFeedMessage {
+            feed_id,
+            price,
+            conf,
+            exponent,
+            publish_time,
+            prev_publish_time: price_update.price_message.publish_time,
+            ema_price: price,
+            ema_conf: conf,
+        };
+        price_update.posted_slot = Clock::get()?.slot;
+        
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum VerificationLevel {
+    Partial { num_signatures: u8 },
+    Full,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, Debug)]
+pub struct PriceFeedMessage {
+    pub feed_id: [u8; 32],
+    pub price: i64,
+    pub conf: u64,
+    pub exponent: i32,
+    pub publish_time: i64,
+    pub prev_publish_time: i64,
+    pub ema_price: i64,
+    pub ema_conf: u64,
+}
+
+#[account]
+pub struct PriceUpdateV2 {
+    pub write_authority: Pubkey,
+    pub verification_level: VerificationLevel,
+    pub price_message: PriceFeedMessage,
+    pub posted_slot: u64,
+}
+
+#[derive(Accounts)]
+pub struct SetPrice<'info> {
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + 32 + 2 + (32 + 8 + 8 + 4 + 8 + 8 + 8 + 8) + 8
+    )]
+    pub price_update: Account<'info, PriceUpdateV2>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}